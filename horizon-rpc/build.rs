@@ -0,0 +1,20 @@
+use std::process::Command;
+
+fn main() {
+    // Record the commit and build time reported by getVersionInfo
+    let commit_hash = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=HORIZON_RPC_COMMIT_HASH={}", commit_hash);
+
+    let build_timestamp = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S");
+    println!("cargo:rustc-env=HORIZON_RPC_BUILD_TIMESTAMP={}", build_timestamp);
+
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs");
+}
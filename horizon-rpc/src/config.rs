@@ -0,0 +1,256 @@
+// Options from the command line, HORIZON_RPC_* environment variables and an
+// optional TOML file.
+//
+// Every option can be given as a flag, e.g. `--horizon-timeout 10`, as an
+// environment variable named after the flag, e.g. `HORIZON_RPC_HORIZON_TIMEOUT=10`,
+// or as a key named after the flag in the file given with `--config`, e.g.
+// `horizon-timeout = 10`. Flags take precedence over environment variables,
+// which take precedence over the file.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::Context;
+use clap::Parser;
+use serde::Deserialize;
+
+use crate::horizon::Strategy;
+
+pub const DEFAULT_BIND_ADDRESS: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 8545));
+pub const DEFAULT_MAX_BATCH_SIZE: u32 = 100;
+
+/// How log lines are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    // One JSON object per line
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => anyhow::bail!("invalid log format {}, expected text or json", s),
+        }
+    }
+}
+
+/// A well-known network, standing in for its passphrase and default Horizon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Network {
+    Testnet,
+    Mainnet,
+    Futurenet,
+}
+
+impl Network {
+    pub fn passphrase(self) -> &'static str {
+        match self {
+            Network::Testnet => "Test SDF Network ; September 2015",
+            Network::Mainnet => "Public Global Stellar Network ; September 2015",
+            Network::Futurenet => "Test SDF Future Network ; October 2022",
+        }
+    }
+
+    pub fn horizon_url(self) -> &'static str {
+        match self {
+            Network::Testnet => "https://horizon-testnet.stellar.org",
+            Network::Mainnet => "https://horizon.stellar.org",
+            Network::Futurenet => "https://horizon-futurenet.stellar.org",
+        }
+    }
+}
+
+impl FromStr for Network {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "testnet" => Ok(Network::Testnet),
+            "mainnet" => Ok(Network::Mainnet),
+            "futurenet" => Ok(Network::Futurenet),
+            _ => anyhow::bail!("invalid network {}, expected testnet, mainnet or futurenet", s),
+        }
+    }
+}
+
+/// A JSON-RPC server that uses Stellar Horizon API as a data source
+#[derive(Parser, Deserialize, Debug, Default)]
+#[clap(author, version, about)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Args {
+    /// A TOML file to read options from, with keys named after the flags
+    #[clap(long, env = "HORIZON_RPC_CONFIG")]
+    #[serde(skip)]
+    pub config: Option<PathBuf>,
+
+    /// The address to bind the JSON-RPC server to [default: 127.0.0.1:8545]
+    #[clap(short, long, env = "HORIZON_RPC_BIND_ADDRESS")]
+    pub bind_address: Option<SocketAddr>,
+
+    /// The Horizon API server URLs, repeated or comma separated [default: the network's Horizon, or https://horizon-testnet.stellar.org]
+    #[clap(long, value_delimiter = ',', env = "HORIZON_RPC_HORIZON_URL")]
+    pub horizon_url: Option<Vec<String>>,
+
+    /// How requests are spread over multiple Horizon URLs: priority or round-robin [default: priority]
+    #[clap(long, env = "HORIZON_RPC_HORIZON_STRATEGY")]
+    pub horizon_strategy: Option<Strategy>,
+
+    /// How many ledgers a Horizon instance may fall behind the others before it is skipped [default: 5]
+    #[clap(long, env = "HORIZON_RPC_HORIZON_MAX_LAG")]
+    pub horizon_max_lag: Option<u32>,
+
+    /// The timeout in seconds for each request to Horizon [default: 30]
+    #[clap(long, env = "HORIZON_RPC_HORIZON_TIMEOUT")]
+    pub horizon_timeout: Option<u64>,
+
+    /// How many times a failed or rate limited Horizon request is retried [default: 3]
+    #[clap(long, env = "HORIZON_RPC_HORIZON_RETRIES")]
+    pub horizon_retries: Option<u32>,
+
+    /// The network to serve: testnet, mainnet or futurenet. Sets the network passphrase Horizon must report
+    #[clap(long, env = "HORIZON_RPC_NETWORK", conflicts_with = "network_passphrase")]
+    pub network: Option<Network>,
+
+    /// The network passphrase Horizon must report, checked at startup and on every health check
+    #[clap(long, env = "HORIZON_RPC_NETWORK_PASSPHRASE")]
+    pub network_passphrase: Option<String>,
+
+    /// How many Horizon responses are cached, 0 to disable the cache [default: 10000]
+    #[clap(long, env = "HORIZON_RPC_CACHE_MAX_ENTRIES")]
+    pub cache_max_entries: Option<usize>,
+
+    /// How many bytes of Horizon responses are cached. Responses over a tenth of this are not cached [default: 104857600]
+    #[clap(long, env = "HORIZON_RPC_CACHE_MAX_BYTES")]
+    pub cache_max_bytes: Option<usize>,
+
+    /// The friendbot URL to report in getNetwork, if the network has one
+    #[clap(long, env = "HORIZON_RPC_FRIENDBOT_URL")]
+    pub friendbot_url: Option<String>,
+
+    /// The maximum age in seconds of the latest ledger before getHealth reports an error [default: 30]
+    #[clap(long, env = "HORIZON_RPC_MAX_LEDGER_AGE")]
+    pub max_ledger_age: Option<u64>,
+
+    /// The most calls a JSON-RPC batch may contain, 0 to reject batches [default: 100]
+    #[clap(long, env = "HORIZON_RPC_MAX_BATCH_SIZE")]
+    pub max_batch_size: Option<u32>,
+
+    /// The largest request body in bytes the server accepts [default: 1048576]
+    #[clap(long, env = "HORIZON_RPC_MAX_REQUEST_BODY_SIZE")]
+    pub max_request_body_size: Option<u32>,
+
+    /// The origins browsers may call the server from, comma separated, or * for any [default: none]
+    #[clap(long, value_delimiter = ',', env = "HORIZON_RPC_CORS_ORIGINS")]
+    pub cors_origins: Option<Vec<String>>,
+
+    /// The most keys a getLedgerEntries call may request [default: 200]
+    #[clap(long, env = "HORIZON_RPC_MAX_LEDGER_ENTRY_KEYS")]
+    pub max_ledger_entry_keys: Option<usize>,
+
+    /// The largest limit paginated methods accept, if lower than each method's own maximum
+    #[clap(long, env = "HORIZON_RPC_MAX_PAGE_LIMIT")]
+    pub max_page_limit: Option<u32>,
+
+    /// The calls per second each IP address may make without a known API key, at least 1. Behind a proxy, see --trusted-proxies [default: unlimited]
+    #[clap(long, env = "HORIZON_RPC_RATE_LIMIT_PER_IP")]
+    pub rate_limit_per_ip: Option<NonZeroU32>,
+
+    /// The calls per second each API key may make, at least 1 [default: unlimited]
+    #[clap(long, env = "HORIZON_RPC_RATE_LIMIT_PER_KEY")]
+    pub rate_limit_per_key: Option<NonZeroU32>,
+
+    /// The API keys clients may send in the X-API-Key header to be limited per key instead of per IP, comma separated
+    #[clap(long, value_delimiter = ',', env = "HORIZON_RPC_API_KEYS", hide_env_values = true)]
+    pub api_keys: Option<Vec<String>>,
+
+    /// The addresses of proxies whose X-Forwarded-For header names the client to rate limit, comma separated. Without any, calls are limited by the address they come from, which behind a proxy is the proxy's
+    #[clap(long, value_delimiter = ',', env = "HORIZON_RPC_TRUSTED_PROXIES")]
+    pub trusted_proxies: Option<Vec<IpAddr>>,
+
+    /// The address to serve Prometheus metrics at /metrics on, if any. May be the bind address
+    #[clap(long, env = "HORIZON_RPC_METRICS_ADDRESS")]
+    pub metrics_address: Option<SocketAddr>,
+
+    /// How log lines are written: text or json [default: text]
+    #[clap(long, env = "HORIZON_RPC_LOG_FORMAT")]
+    pub log_format: Option<LogFormat>,
+
+    /// The OTLP/HTTP endpoint to export request traces to, e.g. http://localhost:4318/v1/traces (optional)
+    #[clap(long, env = "HORIZON_RPC_OTLP_ENDPOINT")]
+    pub otlp_endpoint: Option<String>,
+}
+
+impl Args {
+    /// Parses the command line and environment, filling in options that are
+    /// not set from the config file, if there is one.
+    pub fn load() -> anyhow::Result<Self> {
+        let args = Args::parse();
+        let Some(path) = &args.config else {
+            return Ok(args);
+        };
+
+        let contents =
+            std::fs::read_to_string(path).with_context(|| format!("failed to read config {}", path.display()))?;
+        let file: Args =
+            toml::from_str(&contents).with_context(|| format!("invalid config {}", path.display()))?;
+        let args = args.or(file);
+        if args.network.is_some() && args.network_passphrase.is_some() {
+            anyhow::bail!("network and network-passphrase cannot both be set");
+        }
+        Ok(args)
+    }
+
+    /// The network passphrase Horizon must report, if one is configured.
+    pub fn network_passphrase(&self) -> Option<String> {
+        let preset = self.network.map(|network| network.passphrase().to_string());
+        self.network_passphrase.clone().or(preset)
+    }
+
+    pub fn horizon_url(&self) -> Vec<String> {
+        let network = self.network.unwrap_or(Network::Testnet);
+        self.horizon_url
+            .clone()
+            .unwrap_or_else(|| vec![network.horizon_url().to_string()])
+    }
+
+    // Takes each option from `self` if it is set, otherwise from `other`
+    fn or(self, other: Args) -> Args {
+        Args {
+            config: self.config,
+            bind_address: self.bind_address.or(other.bind_address),
+            horizon_url: self.horizon_url.or(other.horizon_url),
+            horizon_strategy: self.horizon_strategy.or(other.horizon_strategy),
+            horizon_max_lag: self.horizon_max_lag.or(other.horizon_max_lag),
+            horizon_timeout: self.horizon_timeout.or(other.horizon_timeout),
+            horizon_retries: self.horizon_retries.or(other.horizon_retries),
+            network: self.network.or(other.network),
+            network_passphrase: self.network_passphrase.or(other.network_passphrase),
+            cache_max_entries: self.cache_max_entries.or(other.cache_max_entries),
+            cache_max_bytes: self.cache_max_bytes.or(other.cache_max_bytes),
+            friendbot_url: self.friendbot_url.or(other.friendbot_url),
+            max_ledger_age: self.max_ledger_age.or(other.max_ledger_age),
+            max_batch_size: self.max_batch_size.or(other.max_batch_size),
+            max_request_body_size: self.max_request_body_size.or(other.max_request_body_size),
+            cors_origins: self.cors_origins.or(other.cors_origins),
+            max_ledger_entry_keys: self.max_ledger_entry_keys.or(other.max_ledger_entry_keys),
+            max_page_limit: self.max_page_limit.or(other.max_page_limit),
+            rate_limit_per_ip: self.rate_limit_per_ip.or(other.rate_limit_per_ip),
+            rate_limit_per_key: self.rate_limit_per_key.or(other.rate_limit_per_key),
+            api_keys: self.api_keys.or(other.api_keys),
+            trusted_proxies: self.trusted_proxies.or(other.trusted_proxies),
+            metrics_address: self.metrics_address.or(other.metrics_address),
+            log_format: self.log_format.or(other.log_format),
+            otlp_endpoint: self.otlp_endpoint.or(other.otlp_endpoint),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+// A pool of Horizon instances serving the same network, with failover
+// between them.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use serde::Deserialize;
+use url::Url;
+
+/// How requests are spread over the backends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    // Use the first healthy backend in the order they were given
+    #[default]
+    Priority,
+    // Rotate through the healthy backends
+    RoundRobin,
+}
+
+impl FromStr for Strategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "priority" => Ok(Strategy::Priority),
+            "round-robin" => Ok(Strategy::RoundRobin),
+            _ => anyhow::bail!("invalid strategy {}, expected priority or round-robin", s),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Backend {
+    pub url: Url,
+    healthy: AtomicBool,
+    // The ingest_latest_ledger of the last successful health check
+    latest_ledger: AtomicU32,
+    // The network passphrase of the last successful health check
+    network_passphrase: Mutex<Option<String>>,
+}
+
+impl Backend {
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    pub fn latest_ledger(&self) -> u32 {
+        self.latest_ledger.load(Ordering::Relaxed)
+    }
+
+    /// Takes the backend out of rotation until its next successful health
+    /// check.
+    pub fn mark_failed(&self) {
+        if self.healthy.swap(false, Ordering::Relaxed) {
+            tracing::warn!("Horizon backend {} failed, skipping it until it recovers", self.url);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Backends {
+    backends: Vec<Backend>,
+    strategy: Strategy,
+    // Backends further than this many ledgers behind the most recent one are
+    // skipped
+    max_ledger_lag: u32,
+    // When set, backends are only used once they have reported this network
+    network_passphrase: Option<String>,
+    next: AtomicUsize,
+}
+
+impl Backends {
+    pub fn new(urls: Vec<Url>, strategy: Strategy, max_ledger_lag: u32, network_passphrase: Option<String>) -> Self {
+        let backends = urls
+            .into_iter()
+            .map(|url| Backend {
+                url,
+                healthy: AtomicBool::new(true),
+                latest_ledger: AtomicU32::new(0),
+                network_passphrase: Mutex::new(None),
+            })
+            .collect();
+        Self {
+            backends,
+            strategy,
+            max_ledger_lag,
+            network_passphrase,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn all(&self) -> &[Backend] {
+        &self.backends
+    }
+
+    /// The backends to try for a request, in order. Healthy backends come
+    /// first, but unhealthy ones are still included so that a request is
+    /// attempted even when every backend looks down. Backends not known to be
+    /// on the configured network are never included.
+    pub fn candidates(&self) -> Vec<&Backend> {
+        let start = match self.strategy {
+            Strategy::Priority => 0,
+            Strategy::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % self.backends.len(),
+        };
+        let mut candidates: Vec<&Backend> = self.backends[start..]
+            .iter()
+            .chain(&self.backends[..start])
+            .filter(|backend| self.is_on_network(backend))
+            .collect();
+        // A stable sort keeps the strategy's order within each group
+        candidates.sort_by_key(|backend| !backend.is_healthy());
+        candidates
+    }
+
+    /// Records the network passphrase a backend reported, returning whether it
+    /// is the configured one.
+    pub fn record_network(&self, backend: &Backend, passphrase: &str) -> bool {
+        let mut reported = backend.network_passphrase.lock().unwrap();
+        let on_network = match &self.network_passphrase {
+            Some(expected) if expected != passphrase => {
+                if reported.as_deref() != Some(passphrase) {
+                    tracing::error!(
+                        "Horizon backend {} is on network {:?}, not {:?}, refusing to use it",
+                        backend.url,
+                        passphrase,
+                        expected
+                    );
+                }
+                false
+            }
+            _ => true,
+        };
+        *reported = Some(passphrase.to_string());
+        on_network
+    }
+
+    fn is_on_network(&self, backend: &Backend) -> bool {
+        match &self.network_passphrase {
+            Some(expected) => backend.network_passphrase.lock().unwrap().as_ref() == Some(expected),
+            None => true,
+        }
+    }
+
+    /// The backends that reported a network other than the configured one,
+    /// with the passphrase each reported.
+    pub fn on_wrong_network(&self) -> Vec<(&Backend, String)> {
+        let Some(expected) = &self.network_passphrase else {
+            return Vec::new();
+        };
+        self.backends
+            .iter()
+            .filter_map(|backend| {
+                let reported = backend.network_passphrase.lock().unwrap().clone()?;
+                (reported != *expected).then_some((backend, reported))
+            })
+            .collect()
+    }
+
+    /// Records the results of a round of health checks, with the latest
+    /// ingested ledger of each backend or `None` if its check failed. Returns
+    /// the most recent ledger any backend has ingested.
+    pub fn record_health(&self, latest_ledgers: &[Option<u32>]) -> Option<u32> {
+        let newest = latest_ledgers.iter().flatten().copied().max();
+        for (backend, latest_ledger) in self.backends.iter().zip(latest_ledgers) {
+            let healthy = match (latest_ledger, newest) {
+                (Some(ledger), Some(newest)) => {
+                    backend.latest_ledger.store(*ledger, Ordering::Relaxed);
+                    ledger.saturating_add(self.max_ledger_lag) >= newest
+                }
+                _ => false,
+            };
+            let was_healthy = backend.healthy.swap(healthy, Ordering::Relaxed);
+            if healthy != was_healthy {
+                if healthy {
+                    tracing::info!("Horizon backend {} recovered", backend.url);
+                } else {
+                    tracing::warn!(
+                        "Horizon backend {} is unhealthy at ledger {}, skipping it",
+                        backend.url,
+                        backend.latest_ledger()
+                    );
+                }
+            }
+        }
+        newest
+    }
+}
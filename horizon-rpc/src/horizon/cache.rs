@@ -0,0 +1,169 @@
+// In-memory cache of Horizon response bodies, keyed by request path.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::metrics;
+
+pub const DEFAULT_MAX_ENTRIES: usize = 10_000;
+pub const DEFAULT_MAX_BYTES: usize = 100 * 1024 * 1024;
+// Bodies larger than this fraction of the byte budget are not cached, so that
+// one large page cannot flush everything else
+const MAX_BODY_FRACTION: usize = 10;
+// How long the root resource is reused. Kept short because it is how the
+// cache learns that a new ledger has closed.
+pub const ROOT_TTL: Duration = Duration::from_secs(1);
+// How long views of the latest state are reused, at most one ledger close
+pub const LATEST_TTL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lifetime {
+    // The resource never changes once it exists
+    Forever,
+    // The resource reflects the latest ledger and is dropped when a newer
+    // ledger is observed or the TTL passes
+    Latest(Duration),
+}
+
+impl Lifetime {
+    fn of(path: &str) -> Self {
+        let (resource, query) = path.split_once('?').unwrap_or((path, ""));
+        if !query.is_empty() {
+            return Lifetime::Latest(LATEST_TTL);
+        }
+
+        match resource.split_once('/') {
+            // Ledgers are only served once they have closed
+            Some(("ledgers", sequence)) if sequence.parse::<u32>().is_ok() => Lifetime::Forever,
+            // Transactions are only served once they are in a closed ledger
+            Some(("transactions", hash)) if hash.len() == 64 => Lifetime::Forever,
+            _ if resource.is_empty() => Lifetime::Latest(ROOT_TTL),
+            _ => Lifetime::Latest(LATEST_TTL),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    body: String,
+    lifetime: Lifetime,
+    inserted: Instant,
+    // The entry's key in `State::order`
+    order: u64,
+}
+
+impl Entry {
+    fn is_fresh(&self, now: Instant) -> bool {
+        match self.lifetime {
+            Lifetime::Forever => true,
+            Lifetime::Latest(ttl) => now.duration_since(self.inserted) < ttl,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<String, Entry>,
+    // The paths of the entries, oldest first
+    order: BTreeMap<u64, String>,
+    next_order: u64,
+    // The total size of the cached paths and bodies
+    bytes: usize,
+    latest_ledger: u32,
+}
+
+impl State {
+    fn remove(&mut self, path: &str) {
+        if let Some(entry) = self.entries.remove(path) {
+            self.order.remove(&entry.order);
+            self.bytes -= path.len() + entry.body.len();
+        }
+    }
+
+    fn remove_oldest(&mut self) {
+        if let Some((_, path)) = self.order.pop_first() {
+            if let Some(entry) = self.entries.remove(&path) {
+                self.bytes -= path.len() + entry.body.len();
+            }
+        }
+    }
+}
+
+/// Caches successful Horizon response bodies, keeping immutable resources
+/// until evicted and dropping views of the latest state when a new ledger
+/// closes. When either the number of entries or their total size is at its
+/// limit, the oldest entries are evicted first.
+#[derive(Debug)]
+pub struct ResponseCache {
+    max_entries: usize,
+    max_bytes: usize,
+    state: Mutex<State>,
+}
+
+impl ResponseCache {
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            max_entries,
+            max_bytes,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        let body = state
+            .entries
+            .get(path)
+            .filter(|entry| entry.is_fresh(Instant::now()))
+            .map(|entry| entry.body.clone());
+        metrics::observe_cache(body.is_some());
+        body
+    }
+
+    pub fn insert(&self, path: &str, body: String) {
+        let size = path.len() + body.len();
+        if self.max_entries == 0 || size > self.max_bytes / MAX_BODY_FRACTION {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.remove(path);
+        while state.entries.len() >= self.max_entries || state.bytes + size > self.max_bytes {
+            state.remove_oldest();
+        }
+
+        let order = state.next_order;
+        state.next_order += 1;
+        state.order.insert(order, path.to_string());
+        state.bytes += size;
+        state.entries.insert(
+            path.to_string(),
+            Entry {
+                body,
+                lifetime: Lifetime::of(path),
+                inserted: Instant::now(),
+                order,
+            },
+        );
+    }
+
+    /// Records the latest ledger Horizon reported, dropping every view of
+    /// the latest state if it is newer than the last one seen. The root
+    /// resource the ledger was read from is kept.
+    pub fn observe_ledger(&self, ledger: u32) {
+        let mut state = self.state.lock().unwrap();
+        if ledger > state.latest_ledger {
+            state.latest_ledger = ledger;
+            let stale: Vec<String> = state
+                .entries
+                .iter()
+                .filter(|(path, entry)| entry.lifetime != Lifetime::Forever && !path.is_empty())
+                .map(|(path, _)| path.clone())
+                .collect();
+            for path in stale {
+                state.remove(&path);
+            }
+        }
+    }
+}
@@ -1,42 +1,286 @@
-use anyhow::Result;
-use reqwest::Client as ReqwestClient;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::{Stream, StreamExt};
+use reqwest::header::{HeaderMap, ACCEPT, RETRY_AFTER};
+use reqwest::{Client as ReqwestClient, RequestBuilder, Response as HttpResponse, StatusCode};
 use serde::de::DeserializeOwned;
+use tracing::field::Empty;
+use tracing::{Instrument, Span};
 use url::Url;
 
-use crate::models::horizon::{LedgerResponse, Response, RootResponse};
+use crate::horizon::backend::{Backend, Backends, Strategy};
+use crate::horizon::cache::{ResponseCache, DEFAULT_MAX_BYTES, DEFAULT_MAX_ENTRIES};
+use crate::horizon::error::{HorizonError, Result};
+use crate::horizon::sse::{self, Record};
+use crate::metrics;
+use crate::models::horizon::{
+    AccountResponse, AsyncTransactionSubmissionResponse, ClaimableBalanceResponse, EffectResponse,
+    FeeStatsResponse, LedgerResponse, LiquidityPoolResponse, OfferResponse, OperationResponse, Problem,
+    Response, RootResponse, TransactionResponse,
+};
+
+/// Timeout, retry and cache settings for requests to Horizon.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    pub timeout: Duration,
+    // Retries after the first attempt for connection errors, timeouts, 5xx
+    // and 429 responses
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    // Also the longest rate limit reset that is waited out before giving up
+    pub max_backoff: Duration,
+    pub strategy: Strategy,
+    pub health_check_interval: Duration,
+    // Backends further than this many ledgers behind the most recent one are
+    // skipped
+    pub max_ledger_lag: u32,
+    pub cache_max_entries: usize,
+    // The most bytes of response bodies the cache holds
+    pub cache_max_bytes: usize,
+    // When set, only backends that report this network passphrase are used
+    pub network_passphrase: Option<String>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            strategy: Strategy::Priority,
+            health_check_interval: Duration::from_secs(5),
+            max_ledger_lag: 5,
+            cache_max_entries: DEFAULT_MAX_ENTRIES,
+            cache_max_bytes: DEFAULT_MAX_BYTES,
+            network_passphrase: None,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct HorizonClient {
-    base_url: Url,
+    backends: Arc<Backends>,
     client: ReqwestClient,
+    // Streams stay open indefinitely, so only connecting is subject to the
+    // timeout
+    stream_client: ReqwestClient,
+    config: ClientConfig,
+    cache: Arc<ResponseCache>,
 }
 
 impl HorizonClient {
-    pub fn new(base_url: String) -> Result<Self> {
-        let base_url = Url::parse(&base_url)?;
-        let client = ReqwestClient::new();
-        Ok(Self { base_url, client })
+    /// Creates a client for one or more Horizon instances serving the same
+    /// network. Requests fail over between them according to the configured
+    /// strategy.
+    pub fn new(base_urls: &[String], config: ClientConfig) -> Result<Self> {
+        if base_urls.is_empty() {
+            return Err(HorizonError::NoBackends);
+        }
+        let urls = base_urls
+            .iter()
+            .map(|url| Url::parse(url))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let backends = Arc::new(Backends::new(
+            urls,
+            config.strategy,
+            config.max_ledger_lag,
+            config.network_passphrase.clone(),
+        ));
+        let client = ReqwestClient::builder().timeout(config.timeout).build()?;
+        let stream_client = ReqwestClient::builder().connect_timeout(config.timeout).build()?;
+        let cache = Arc::new(ResponseCache::new(config.cache_max_entries, config.cache_max_bytes));
+        Ok(Self {
+            backends,
+            client,
+            stream_client,
+            config,
+            cache,
+        })
+    }
+
+    /// Starts checking the root resource of every backend in the background,
+    /// taking backends that fail or fall behind out of rotation until they
+    /// recover.
+    pub fn spawn_health_checks(&self) -> tokio::task::JoinHandle<()> {
+        let client = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(client.config.health_check_interval);
+            loop {
+                interval.tick().await;
+                client.check_backends().await;
+            }
+        })
+    }
+
+    /// Checks every backend once, failing if any reports a different network
+    /// than the configured passphrase. Backends that cannot be reached are
+    /// only used once a background health check verifies their network.
+    pub async fn verify_network(&self) -> Result<()> {
+        self.check_backends().await;
+        match self.backends.on_wrong_network().into_iter().next() {
+            Some((backend, reported)) => Err(HorizonError::WrongNetwork {
+                url: backend.url.to_string(),
+                expected: self.config.network_passphrase.clone().unwrap_or_default(),
+                reported,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    async fn check_backends(&self) {
+        let checks = self.backends.all().iter().map(|backend| self.check_health(backend));
+        let latest_ledgers = futures::future::join_all(checks).await;
+        if let Some(newest) = self.backends.record_health(&latest_ledgers) {
+            self.cache.observe_ledger(newest);
+        }
+    }
+
+    // Fetches a backend's root resource, bypassing the cache and retries, and
+    // returns its latest ingested ledger. Backends on the wrong network fail.
+    async fn check_health(&self, backend: &Backend) -> Option<u32> {
+        let response = self.client.get(backend.url.clone()).send().await.ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        let root = response.json::<RootResponse>().await.ok()?;
+        if !self.backends.record_network(backend, &root.network_passphrase) {
+            return None;
+        }
+        metrics::observe_ledger(root.history_latest_ledger, &root.history_latest_ledger_closed_at);
+        Some(root.ingest_latest_ledger)
     }
 
     pub async fn get<T>(&self, path: &str) -> Result<T>
     where
         T: DeserializeOwned,
     {
-        let url = self.base_url.join(path)?;
-        let response = self.client.get(url).send().await?;
-        
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            anyhow::bail!("HTTP error {}: {}", status, error_text);
+        let body = self.fetch(path).await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Like `get`, but returns `None` when Horizon responds with 404 Not Found.
+    pub async fn get_optional<T>(&self, path: &str) -> Result<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        match self.fetch(path).await {
+            Ok(body) => Ok(Some(serde_json::from_str(&body)?)),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
         }
-        
-        let data = response.json::<T>().await?;
-        Ok(data)
+    }
+
+    // Returns the body of a successful response, from the cache if possible
+    async fn fetch(&self, path: &str) -> Result<String> {
+        if let Some(body) = self.cache.get(path) {
+            tracing::debug!("Served Horizon response {} from cache", path);
+            return Ok(body);
+        }
+
+        let response = self.send(|base_url| Ok(self.client.get(base_url.join(path)?))).await?;
+        let body = error_for_status(response).await?.text().await?;
+
+        self.cache.insert(path, body.clone());
+        Ok(body)
+    }
+
+    // Opens a server-sent event stream of the records of a collection
+    async fn open_stream<T>(&self, path: &str) -> Result<impl Stream<Item = Result<T>>>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self
+            .send(|base_url| {
+                Ok(self
+                    .stream_client
+                    .get(base_url.join(path)?)
+                    .header(ACCEPT, "text/event-stream"))
+            })
+            .await?;
+        let response = error_for_status(response).await?;
+
+        Ok(sse::records(response.bytes_stream()))
+    }
+
+    // Sends a request, retrying on connection errors, timeouts, 5xx and 429
+    // responses. Each retry goes to the next backend, and once every backend
+    // has been tried the client backs off exponentially, or for the reset
+    // Horizon asks for on 429 responses. Returns the last response once
+    // retries run out.
+    async fn send(&self, request: impl Fn(&Url) -> Result<RequestBuilder>) -> Result<HttpResponse> {
+        let candidates = self.backends.candidates();
+        if candidates.is_empty() {
+            return Err(HorizonError::NoBackendOnNetwork);
+        }
+        let max_attempts = (self.config.max_retries + 1).max(candidates.len() as u32);
+        let mut attempt = 0;
+        loop {
+            let backend = candidates[attempt as usize % candidates.len()];
+            let (client, http_request) = request(&backend.url)?.build_split();
+            let http_request = http_request?;
+            let span = tracing::info_span!(
+                "horizon_request",
+                otel.name = %format!("{} {}", http_request.method(), http_request.url().path()),
+                otel.kind = "client",
+                otel.status_code = Empty,
+                method = %http_request.method(),
+                url = %http_request.url(),
+                attempt,
+                status = Empty,
+            );
+            let started_at = Instant::now();
+            let result = client.execute(http_request).instrument(span.clone()).await;
+            metrics::observe_upstream(&backend.url, started_at, &result);
+            record_result(&span, started_at, &result);
+
+            let backoff = self.backoff(attempt / candidates.len() as u32);
+            let delay = match &result {
+                Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    Some(rate_limit_reset(response.headers()).unwrap_or(backoff))
+                }
+                Ok(response) if response.status().is_server_error() => {
+                    backend.mark_failed();
+                    Some(backoff)
+                }
+                Ok(_) => None,
+                Err(e) if e.is_timeout() || e.is_connect() || e.is_request() => {
+                    backend.mark_failed();
+                    Some(backoff)
+                }
+                Err(_) => None,
+            };
+
+            match delay {
+                Some(delay) if attempt + 1 < max_attempts => {
+                    attempt += 1;
+                    if (attempt as usize).is_multiple_of(candidates.len()) {
+                        if delay > self.config.max_backoff {
+                            // Not worth waiting for
+                            return Ok(result?);
+                        }
+                        tracing::debug!("Retrying Horizon request in {:?}", delay);
+                        tokio::time::sleep(delay).await;
+                    }
+                }
+                _ => return Ok(result?),
+            }
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.config
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.config.max_backoff)
     }
 
     pub async fn get_root(&self) -> Result<RootResponse> {
-        self.get::<RootResponse>("").await
+        let root = self.get::<RootResponse>("").await?;
+        self.cache.observe_ledger(root.history_latest_ledger);
+        Ok(root)
     }
 
     pub async fn get_latest_ledger(&self) -> Result<LedgerResponse> {
@@ -46,7 +290,7 @@ impl HorizonClient {
                 return Ok(record);
             }
         }
-        anyhow::bail!("No ledger found in response")
+        Err(HorizonError::InvalidResponse("no ledger found in response".to_string()))
     }
 
     pub async fn get_ledger(&self, sequence: u32) -> Result<LedgerResponse> {
@@ -78,6 +322,266 @@ impl HorizonClient {
         self.get::<Response<LedgerResponse>>(&query).await
     }
 
+    /// Fetches operations across all ledgers. `join` is passed through to
+    /// Horizon, e.g. `Some("transactions")` embeds each operation's transaction.
+    pub async fn get_operations(
+        &self,
+        cursor: Option<String>,
+        limit: Option<u32>,
+        order: Option<&str>,
+        join: Option<&str>,
+    ) -> Result<Response<OperationResponse>> {
+        let mut params = Vec::new();
+
+        if let Some(cursor) = cursor {
+            params.push(format!("cursor={}", cursor));
+        }
+
+        if let Some(limit) = limit {
+            params.push(format!("limit={}", limit));
+        }
+
+        if let Some(order) = order {
+            params.push(format!("order={}", order));
+        }
+
+        if let Some(join) = join {
+            params.push(format!("join={}", join));
+        }
+
+        let query = if params.is_empty() {
+            "operations".to_string()
+        } else {
+            format!("operations?{}", params.join("&"))
+        };
+
+        self.get::<Response<OperationResponse>>(&query).await
+    }
+
+    pub async fn get_account(&self, account_id: &str) -> Result<Option<AccountResponse>> {
+        let path = format!("accounts/{}", account_id);
+        self.get_optional::<AccountResponse>(&path).await
+    }
+
+    pub async fn get_offer(&self, offer_id: i64) -> Result<Option<OfferResponse>> {
+        let path = format!("offers/{}", offer_id);
+        self.get_optional::<OfferResponse>(&path).await
+    }
+
+    pub async fn get_claimable_balance(&self, balance_id: &str) -> Result<Option<ClaimableBalanceResponse>> {
+        let path = format!("claimable_balances/{}", balance_id);
+        self.get_optional::<ClaimableBalanceResponse>(&path).await
+    }
+
+    pub async fn get_liquidity_pool(&self, pool_id: &str) -> Result<Option<LiquidityPoolResponse>> {
+        let path = format!("liquidity_pools/{}", pool_id);
+        self.get_optional::<LiquidityPoolResponse>(&path).await
+    }
+
+    pub async fn get_transactions(&self, cursor: Option<String>, limit: Option<u32>, order: Option<&str>) -> Result<Response<TransactionResponse>> {
+        let mut params = Vec::new();
+
+        if let Some(cursor) = cursor {
+            params.push(format!("cursor={}", cursor));
+        }
+
+        if let Some(limit) = limit {
+            params.push(format!("limit={}", limit));
+        }
+
+        if let Some(order) = order {
+            params.push(format!("order={}", order));
+        }
+
+        let query = if params.is_empty() {
+            "transactions".to_string()
+        } else {
+            format!("transactions?{}", params.join("&"))
+        };
+
+        self.get::<Response<TransactionResponse>>(&query).await
+    }
+
+    pub async fn get_transaction(&self, hash: &str) -> Result<Option<TransactionResponse>> {
+        let path = format!("transactions/{}", hash);
+        self.get_optional::<TransactionResponse>(&path).await
+    }
+
+    /// Submits a base64 encoded transaction envelope without waiting for it
+    /// to be included in a ledger.
+    pub async fn submit_transaction_async(&self, envelope_xdr: &str) -> Result<AsyncTransactionSubmissionResponse> {
+        let response = self
+            .send(|base_url| Ok(self.client.post(base_url.join("transactions_async")?).form(&[("tx", envelope_xdr)])))
+            .await?;
+
+        // Horizon answers rejected, duplicate and throttled submissions with
+        // non-2xx statuses that still carry a submission status in the body
+        let status = response.status();
+        let body = response.text().await?;
+        match serde_json::from_str::<AsyncTransactionSubmissionResponse>(&body) {
+            Ok(submission) => Ok(submission),
+            Err(_) => Err(HorizonError::Status {
+                status,
+                problem_type: serde_json::from_str::<Problem>(&body).ok().map(|p| p.type_),
+                body,
+            }),
+        }
+    }
+
+    pub async fn get_fee_stats(&self) -> Result<FeeStatsResponse> {
+        self.get::<FeeStatsResponse>("fee_stats").await
+    }
+
+    /// Streams the records of a collection as Horizon ingests them, starting
+    /// after `cursor`, which may be `now` to only receive new records. `path`
+    /// may carry other query parameters, e.g. `operations?join=transactions`.
+    ///
+    /// Fails if the first connection fails. After that, dropped connections
+    /// are reopened after the last record received, backing off until a
+    /// record arrives, so the stream only ends if Horizon rejects it.
+    pub async fn stream<T>(&self, path: &str, cursor: &str) -> Result<impl Stream<Item = Result<T>>>
+    where
+        T: Record + Send + 'static,
+    {
+        let records = self.open_stream::<T>(&cursor_path(path, cursor)).await?;
+        let state = Tail {
+            client: self.clone(),
+            path: path.to_string(),
+            cursor: cursor.to_string(),
+            records: Some(Box::pin(records)),
+            reconnects: 0,
+            done: false,
+        };
+
+        Ok(futures::stream::unfold(state, |mut state| async move {
+            while !state.done {
+                if let Some(records) = state.records.as_mut() {
+                    match records.next().await {
+                        Some(Ok(record)) => {
+                            state.cursor = record.paging_token().to_string();
+                            state.reconnects = 0;
+                            return Some((Ok(record), state));
+                        }
+                        // The connection is fine, only this record is not
+                        Some(Err(e @ HorizonError::InvalidResponse(_))) => return Some((Err(e), state)),
+                        Some(Err(e)) => {
+                            tracing::warn!("Horizon stream {} disconnected: {}", state.path, e);
+                            state.records = None;
+                        }
+                        // Horizon closes streams from time to time
+                        None => state.records = None,
+                    }
+                    continue;
+                }
+
+                tokio::time::sleep(state.client.backoff(state.reconnects)).await;
+                state.reconnects += 1;
+                match state.client.open_stream(&cursor_path(&state.path, &state.cursor)).await {
+                    Ok(records) => state.records = Some(Box::pin(records)),
+                    Err(e @ HorizonError::Status { status, .. }) if status.is_client_error() => {
+                        state.done = true;
+                        return Some((Err(e), state));
+                    }
+                    Err(e) => tracing::warn!("Failed to reconnect Horizon stream {}: {}", state.path, e),
+                }
+            }
+            None
+        }))
+    }
+
+    pub async fn stream_ledgers(&self, cursor: &str) -> Result<impl Stream<Item = Result<LedgerResponse>>> {
+        self.stream("ledgers", cursor).await
+    }
+
+    pub async fn stream_transactions(&self, cursor: &str) -> Result<impl Stream<Item = Result<TransactionResponse>>> {
+        self.stream("transactions", cursor).await
+    }
+
+    /// `join` is passed through to Horizon as in `get_operations`.
+    pub async fn stream_operations(
+        &self,
+        cursor: &str,
+        join: Option<&str>,
+    ) -> Result<impl Stream<Item = Result<OperationResponse>>> {
+        match join {
+            Some(join) => self.stream(&format!("operations?join={}", join), cursor).await,
+            None => self.stream("operations", cursor).await,
+        }
+    }
+
+    pub async fn stream_effects(&self, cursor: &str) -> Result<impl Stream<Item = Result<EffectResponse>>> {
+        self.stream("effects", cursor).await
+    }
+
     // Add more methods to interact with other Horizon endpoints as needed
-    // For example, for accounts, transactions, operations, effects, etc.
-}
\ No newline at end of file
+    // For example, for effects, trades, etc.
+}
+
+// The state of a stream from `HorizonClient::stream`
+struct Tail<T> {
+    client: HorizonClient,
+    path: String,
+    // Where to resume after a disconnect
+    cursor: String,
+    // The open connection, if any
+    records: Option<Pin<Box<dyn Stream<Item = Result<T>> + Send>>>,
+    // Reconnects since the last record arrived
+    reconnects: u32,
+    done: bool,
+}
+
+fn cursor_path(path: &str, cursor: &str) -> String {
+    let separator = if path.contains('?') { '&' } else { '?' };
+    format!("{}{}cursor={}", path, separator, cursor)
+}
+
+// Turns an unsuccessful response into the matching error
+async fn error_for_status(response: HttpResponse) -> Result<HttpResponse> {
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(HorizonError::RateLimited {
+            retry_after: rate_limit_reset(response.headers()),
+        });
+    }
+    if !status.is_success() {
+        let body = response.text().await?;
+        let problem_type = serde_json::from_str::<Problem>(&body).ok().map(|p| p.type_);
+        return Err(HorizonError::Status {
+            status,
+            problem_type,
+            body,
+        });
+    }
+    Ok(response)
+}
+
+// Records the outcome of a request on its span, and logs it with how long
+// it took. Only failures that are retried count as errors, since a 404 is
+// often an answer, e.g. for an account that does not exist.
+fn record_result(span: &Span, started_at: Instant, result: &reqwest::Result<HttpResponse>) {
+    let duration_ms = started_at.elapsed().as_millis() as u64;
+    let _entered = span.enter();
+    match result {
+        Ok(response) => {
+            let status = response.status();
+            span.record("status", status.as_u16());
+            if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+                span.record("otel.status_code", "error");
+            }
+            tracing::debug!(duration_ms, "Horizon responded with {}", status);
+        }
+        Err(e) => {
+            span.record("otel.status_code", "error");
+            tracing::debug!(duration_ms, "Horizon request failed: {}", e);
+        }
+    }
+}
+
+// How long Horizon asked clients to wait before retrying a rate limited
+// request. Both headers carry a number of seconds.
+fn rate_limit_reset(headers: &HeaderMap) -> Option<Duration> {
+    [RETRY_AFTER.as_str(), "x-ratelimit-reset"]
+        .iter()
+        .find_map(|name| headers.get(*name)?.to_str().ok()?.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
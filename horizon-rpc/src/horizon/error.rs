@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, HorizonError>;
+
+#[derive(Debug, Error)]
+pub enum HorizonError {
+    #[error("invalid Horizon URL: {0}")]
+    Url(#[from] url::ParseError),
+
+    #[error("at least one Horizon URL is required")]
+    NoBackends,
+
+    #[error("Horizon backend {url} is on network {reported:?}, not {expected:?}")]
+    WrongNetwork {
+        url: String,
+        expected: String,
+        reported: String,
+    },
+
+    #[error("no Horizon backend is known to be on the configured network")]
+    NoBackendOnNetwork,
+
+    #[error("request to Horizon timed out")]
+    Timeout,
+
+    #[error("failed to reach Horizon: {0}")]
+    Request(reqwest::Error),
+
+    #[error("Horizon rate limit exceeded")]
+    RateLimited {
+        // How long Horizon asked clients to wait, if it said
+        retry_after: Option<Duration>,
+    },
+
+    #[error("HTTP error {status}: {body}")]
+    Status {
+        status: StatusCode,
+        // The `type` of the problem document Horizon returned, if any
+        problem_type: Option<String>,
+        body: String,
+    },
+
+    #[error("invalid response from Horizon: {0}")]
+    InvalidResponse(String),
+}
+
+impl HorizonError {
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, HorizonError::Status { status, .. } if *status == StatusCode::NOT_FOUND)
+    }
+}
+
+impl From<reqwest::Error> for HorizonError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            HorizonError::Timeout
+        } else {
+            HorizonError::Request(e)
+        }
+    }
+}
+
+impl From<serde_json::Error> for HorizonError {
+    fn from(e: serde_json::Error) -> Self {
+        HorizonError::InvalidResponse(e.to_string())
+    }
+}
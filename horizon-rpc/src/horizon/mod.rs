@@ -1,3 +1,8 @@
+pub mod backend;
+pub mod cache;
 pub mod client;
+pub mod error;
+pub mod sse;
 
-pub use client::HorizonClient;
\ No newline at end of file
+pub use backend::Strategy;
+pub use client::{ClientConfig, HorizonClient};
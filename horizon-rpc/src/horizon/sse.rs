@@ -0,0 +1,107 @@
+// Parsing of Horizon's server-sent event streams.
+//
+// Horizon streams a collection when it is requested with
+// `Accept: text/event-stream`. Each record arrives as an event whose `id` is
+// the record's paging token and whose `data` is the record's JSON. The
+// stream starts with an `open` event and may carry comments and `retry`
+// fields, which are skipped.
+
+use std::collections::VecDeque;
+
+use futures::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+
+use crate::horizon::error::{HorizonError, Result};
+use crate::models::horizon::{EffectResponse, LedgerResponse, OperationResponse, TransactionResponse};
+
+/// A record of a collection Horizon can stream.
+pub trait Record: DeserializeOwned {
+    /// The cursor to resume the stream after this record.
+    fn paging_token(&self) -> &str;
+}
+
+macro_rules! impl_record {
+    ($($ty:ty),*) => {
+        $(impl Record for $ty {
+            fn paging_token(&self) -> &str {
+                &self.paging_token
+            }
+        })*
+    };
+}
+
+impl_record!(LedgerResponse, TransactionResponse, OperationResponse, EffectResponse);
+
+/// Turns a response body into the records it streams.
+pub fn records<T, S, B>(body: S) -> impl Stream<Item = Result<T>>
+where
+    T: DeserializeOwned,
+    S: Stream<Item = reqwest::Result<B>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    let state = Parser {
+        body,
+        buffer: Vec::new(),
+        pending: VecDeque::new(),
+        done: false,
+    };
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(data) = state.pending.pop_front() {
+                let record = serde_json::from_str(&data).map_err(HorizonError::from);
+                return Some((record, state));
+            }
+            if state.done {
+                return None;
+            }
+            match state.body.next().await {
+                Some(Ok(chunk)) => state.push(chunk.as_ref()),
+                Some(Err(e)) => {
+                    state.done = true;
+                    return Some((Err(HorizonError::from(e)), state));
+                }
+                None => return None,
+            }
+        }
+    })
+}
+
+struct Parser<S> {
+    body: S,
+    buffer: Vec<u8>,
+    // Data of the complete record events not yet yielded
+    pending: VecDeque<String>,
+    done: bool,
+}
+
+impl<S> Parser<S> {
+    fn push(&mut self, chunk: &[u8]) {
+        self.buffer.extend_from_slice(chunk);
+        // Events are separated by a blank line
+        while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let event: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            if let Some(data) = record_data(&String::from_utf8_lossy(&event)) {
+                self.pending.push_back(data);
+            }
+        }
+    }
+}
+
+// Returns the data of an event if it carries a record
+fn record_data(event: &str) -> Option<String> {
+    let mut event_type = None;
+    let mut data = Vec::new();
+    for line in event.lines() {
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event_type = Some(value),
+            "data" => data.push(value),
+            _ => {}
+        }
+    }
+    match event_type {
+        None | Some("message") if !data.is_empty() => Some(data.join("\n")),
+        _ => None,
+    }
+}
@@ -0,0 +1,11 @@
+// The library behind the horizon-rpc binary, so that the integration tests
+// can run the server against a mock Horizon.
+
+pub mod config;
+pub mod horizon;
+pub mod metrics;
+pub mod models;
+pub mod rate_limit;
+pub mod rpc;
+pub mod server;
+pub mod telemetry;
@@ -1,49 +1,88 @@
-mod horizon;
-mod models;
-mod rpc;
-
-use std::net::SocketAddr;
-
-use clap::Parser;
-use jsonrpsee::server::{ServerBuilder, ServerHandle};
-use log::info;
-use rpc::{StellarRpcApiServer, StellarRpcServer};
-
-/// A JSON-RPC server that uses Stellar Horizon API as a data source
-#[derive(Parser, Debug)]
-#[clap(author, version, about)]
-struct Args {
-    /// The address to bind the JSON-RPC server to
-    #[clap(short, long, default_value = "127.0.0.1:8545")]
-    bind_address: SocketAddr,
-
-    /// The Horizon API server URL
-    #[clap(long, default_value = "https://horizon-testnet.stellar.org")]
-    horizon_url: String,
-}
+use std::time::Duration;
+
+use horizon_rpc::config::{Args, DEFAULT_BIND_ADDRESS, DEFAULT_MAX_BATCH_SIZE};
+use horizon_rpc::horizon::{ClientConfig, HorizonClient};
+use horizon_rpc::metrics;
+use horizon_rpc::rate_limit::RateLimiter;
+use horizon_rpc::rpc::{self, StellarRpcApiServer, StellarRpcServer};
+use horizon_rpc::server::{self, ServerConfig};
+use horizon_rpc::telemetry;
+use jsonrpsee::server::{BatchRequestConfig, ServerHandle};
+use tracing::info;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    env_logger::init();
+    let args = Args::load()?;
+    let telemetry = telemetry::init(args.log_format.unwrap_or_default(), args.otlp_endpoint.as_deref())?;
 
-    let args = Args::parse();
-    let horizon_url = args.horizon_url;
-    let bind_address = args.bind_address;
+    let horizon_url = args.horizon_url();
+    let network_passphrase = args.network_passphrase();
+    let bind_address = args.bind_address.unwrap_or(DEFAULT_BIND_ADDRESS);
 
     // Create the RPC server with the Horizon client
-    let rpc = StellarRpcServer::new(horizon_url.clone())?;
-
-    // Build the JSON-RPC server
-    let server = ServerBuilder::default().build(bind_address).await?;
-
-    // Register the RPC API methods
-    let server_handle = server.start(rpc.into_rpc());
-
-    info!("JSON-RPC server started at {}", bind_address);
-    info!("Using Horizon API at {}", horizon_url);
+    let defaults = ClientConfig::default();
+    let config = ClientConfig {
+        timeout: args.horizon_timeout.map(Duration::from_secs).unwrap_or(defaults.timeout),
+        max_retries: args.horizon_retries.unwrap_or(defaults.max_retries),
+        strategy: args.horizon_strategy.unwrap_or(defaults.strategy),
+        max_ledger_lag: args.horizon_max_lag.unwrap_or(defaults.max_ledger_lag),
+        cache_max_entries: args.cache_max_entries.unwrap_or(defaults.cache_max_entries),
+        cache_max_bytes: args.cache_max_bytes.unwrap_or(defaults.cache_max_bytes),
+        network_passphrase: network_passphrase.clone(),
+        ..defaults
+    };
+    let max_ledger_age = args
+        .max_ledger_age
+        .map(Duration::from_secs)
+        .unwrap_or(rpc::api::DEFAULT_MAX_LEDGER_AGE);
+    let horizon_client = HorizonClient::new(&horizon_url, config)?;
+    if let Some(passphrase) = &network_passphrase {
+        horizon_client.verify_network().await?;
+        info!("Serving network {:?}", passphrase);
+    }
+    horizon_client.spawn_health_checks();
+    let rpc = StellarRpcServer::new(horizon_client)
+        .with_friendbot_url(args.friendbot_url)
+        .with_max_ledger_age(max_ledger_age)
+        .with_max_ledger_entry_keys(args.max_ledger_entry_keys.unwrap_or(rpc::api::DEFAULT_MAX_LEDGER_ENTRY_KEYS))
+        .with_max_page_limit(args.max_page_limit);
+
+    let batch_config = match args.max_batch_size.unwrap_or(DEFAULT_MAX_BATCH_SIZE) {
+        0 => BatchRequestConfig::Disabled,
+        max => BatchRequestConfig::Limit(max),
+    };
+    let rate_limiter = RateLimiter::new(
+        args.rate_limit_per_ip,
+        args.rate_limit_per_key,
+        args.api_keys.unwrap_or_default(),
+    );
+    let server_config = ServerConfig {
+        batch_config,
+        max_request_body_size: args.max_request_body_size.unwrap_or(server::DEFAULT_MAX_REQUEST_BODY_SIZE),
+        cors_origins: args.cors_origins.unwrap_or_default(),
+        rate_limiter,
+        trusted_proxies: args.trusted_proxies.unwrap_or_default(),
+        serve_metrics: args.metrics_address == Some(bind_address),
+    };
+    let (local_addr, server_handle) = server::start(bind_address, rpc.into_rpc(), server_config).await?;
+
+    info!("JSON-RPC server started at {}", local_addr);
+    info!("Using Horizon API at {}", horizon_url.join(", "));
+
+    match args.metrics_address {
+        Some(metrics_address) if metrics_address == bind_address => {
+            info!("Serving metrics at {}/metrics", bind_address);
+        }
+        Some(metrics_address) => {
+            tokio::spawn(metrics::serve(metrics_address)?);
+            info!("Metrics server started at {}", metrics_address);
+        }
+        None => {}
+    }
 
     // Keep the server running until terminated
     wait_for_shutdown(server_handle).await?;
+    telemetry.shutdown();
 
     Ok(())
 }
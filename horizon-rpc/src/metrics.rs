@@ -0,0 +1,198 @@
+// Prometheus metrics, served at `/metrics` on a separate address or alongside
+// the JSON-RPC server.
+//
+// Metrics are registered in the default registry the first time they are
+// used, so series only appear once there is something to report.
+
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::{Arc, LazyLock};
+use std::time::Instant;
+
+use actix_web::{web, App, HttpResponse, HttpServer};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::server::MethodResponse;
+use jsonrpsee::types::Request;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge, HistogramVec, IntCounterVec, IntGauge,
+    TextEncoder,
+};
+use url::Url;
+
+static RPC_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("horizon_rpc_requests_total", "JSON-RPC calls by method", &["method"]).unwrap()
+});
+
+static RPC_ERRORS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "horizon_rpc_request_errors_total",
+        "JSON-RPC calls that returned an error, by method and error code",
+        &["method", "code"]
+    )
+    .unwrap()
+});
+
+static RPC_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "horizon_rpc_request_duration_seconds",
+        "Time to answer JSON-RPC calls, by method",
+        &["method"]
+    )
+    .unwrap()
+});
+
+static UPSTREAM_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "horizon_rpc_upstream_request_duration_seconds",
+        "Time for Horizon to respond, by backend",
+        &["backend"]
+    )
+    .unwrap()
+});
+
+static UPSTREAM_RESPONSES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "horizon_rpc_upstream_responses_total",
+        "Requests to Horizon by backend and HTTP status, or timeout or error if there was no response",
+        &["backend", "status"]
+    )
+    .unwrap()
+});
+
+static CACHE_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "horizon_rpc_cache_requests_total",
+        "Lookups in the Horizon response cache by result, hit or miss",
+        &["result"]
+    )
+    .unwrap()
+});
+
+static LATEST_LEDGER: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("horizon_rpc_latest_ledger", "The latest ledger seen by the Horizon health checks").unwrap()
+});
+
+static LATEST_LEDGER_CLOSED_AT: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "horizon_rpc_latest_ledger_closed_at_seconds",
+        "When the latest ledger seen closed, as a Unix timestamp"
+    )
+    .unwrap()
+});
+
+static LATEST_LEDGER_AGE: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "horizon_rpc_latest_ledger_age_seconds",
+        "Seconds between when the latest ledger seen closed and the time of the scrape"
+    )
+    .unwrap()
+});
+
+/// JSON-RPC middleware that records each call.
+#[derive(Debug, Clone)]
+pub struct RpcMetrics<S> {
+    service: S,
+    // Calls to other methods are recorded as `unknown`, so that clients
+    // cannot create series
+    methods: Arc<HashSet<String>>,
+}
+
+impl<S> RpcMetrics<S> {
+    pub fn new(service: S, methods: Arc<HashSet<String>>) -> Self {
+        Self { service, methods }
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for RpcMetrics<S>
+where
+    S: RpcServiceT<'a> + Send + Sync,
+    S::Future: 'a,
+{
+    type Future = BoxFuture<'a, MethodResponse>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let method = if self.methods.contains(request.method_name()) {
+            request.method_name().to_string()
+        } else {
+            "unknown".to_string()
+        };
+        let started_at = Instant::now();
+        let response = self.service.call(request);
+
+        async move {
+            let response = response.await;
+            RPC_REQUESTS.with_label_values(&[&method]).inc();
+            RPC_DURATION
+                .with_label_values(&[&method])
+                .observe(started_at.elapsed().as_secs_f64());
+            if let Some(code) = response.as_error_code() {
+                RPC_ERRORS.with_label_values(&[&method, &code.to_string()]).inc();
+            }
+            response
+        }
+        .boxed()
+    }
+}
+
+/// Records a request to a Horizon backend that was sent at `started_at`.
+pub fn observe_upstream(backend: &Url, started_at: Instant, result: &reqwest::Result<reqwest::Response>) {
+    let status = match result {
+        Ok(response) => response.status().as_u16().to_string(),
+        Err(e) if e.is_timeout() => "timeout".to_string(),
+        Err(_) => "error".to_string(),
+    };
+    UPSTREAM_DURATION
+        .with_label_values(&[backend.as_str()])
+        .observe(started_at.elapsed().as_secs_f64());
+    UPSTREAM_RESPONSES
+        .with_label_values(&[backend.as_str(), &status])
+        .inc();
+}
+
+/// Records a lookup in the response cache.
+pub fn observe_cache(hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    CACHE_REQUESTS.with_label_values(&[result]).inc();
+}
+
+/// Records a ledger a backend has closed, if it is newer than any seen.
+pub fn observe_ledger(sequence: u32, closed_at: &str) {
+    if i64::from(sequence) <= LATEST_LEDGER.get() {
+        return;
+    }
+    LATEST_LEDGER.set(sequence.into());
+    match DateTime::parse_from_rfc3339(closed_at) {
+        Ok(closed_at) => LATEST_LEDGER_CLOSED_AT.set(closed_at.timestamp()),
+        Err(e) => tracing::warn!("Invalid close time {} for ledger {}: {}", closed_at, sequence, e),
+    }
+}
+
+/// Renders every metric in the Prometheus text format.
+pub fn render() -> prometheus::Result<String> {
+    if LATEST_LEDGER.get() > 0 {
+        LATEST_LEDGER_AGE.set(Utc::now().timestamp() - LATEST_LEDGER_CLOSED_AT.get());
+    }
+    TextEncoder::new().encode_to_string(&prometheus::gather())
+}
+
+async fn metrics() -> HttpResponse {
+    match render() {
+        Ok(body) => HttpResponse::Ok()
+            .content_type(prometheus::TEXT_FORMAT)
+            .body(body),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// Serves `/metrics` at `address` until the returned server is stopped.
+pub fn serve(address: SocketAddr) -> anyhow::Result<actix_web::dev::Server> {
+    let server = HttpServer::new(|| App::new().route("/metrics", web::get().to(metrics)))
+        .workers(1)
+        .disable_signals()
+        .bind(address)?
+        .run();
+    Ok(server)
+}
@@ -7,12 +7,16 @@ pub mod rpc {
     use super::*;
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
     pub struct Health {
         pub status: String,
-        pub horizon_status: String,
+        pub latest_ledger: u32,
+        pub oldest_ledger: u32,
+        pub ledger_retention_window: u32,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
     pub struct Ledger {
         pub hash: String,
         pub sequence: u32,
@@ -24,14 +28,93 @@ pub mod rpc {
         pub protocol_version: u32,
     }
 
+    // A single ledger with the fields of `Ledger` and the rest of what Horizon
+    // knows about it
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct LedgerDetails {
+        pub hash: String,
+        pub sequence: u32,
+        pub closed_at: String,
+        pub successful_transaction_count: u32,
+        pub failed_transaction_count: u32,
+        pub operation_count: u32,
+        pub tx_set_operation_count: u32,
+        pub protocol_version: u32,
+        pub total_coins: String,
+        pub fee_pool: String,
+        pub base_fee_in_stroops: u32,
+        pub base_reserve_in_stroops: u32,
+        pub max_tx_set_size: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub header_xdr: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub header_json: Option<serde_json::Value>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct LedgerInfo {
+        pub hash: String,
+        pub sequence: u32,
+        pub ledger_close_time: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub header_xdr: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub header_json: Option<serde_json::Value>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GetLedgersResult {
+        pub ledgers: Vec<LedgerInfo>,
+        pub latest_ledger: u32,
+        pub latest_ledger_close_time: i64,
+        pub oldest_ledger: u32,
+        pub oldest_ledger_close_time: i64,
+        pub cursor: String,
+    }
+
     #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
     pub struct Network {
         pub passphrase: String,
         pub protocol_version: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub friendbot_url: Option<String>,
+    }
+
+    /// Encoding used for XDR values in responses.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum XdrFormat {
+        #[default]
+        Base64,
+        Json,
+    }
+
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    pub struct Pagination {
+        pub cursor: Option<String>,
+        pub limit: Option<u32>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct EventFilter {
+        // Comma separated list of "system", "contract" and "diagnostic"
+        #[serde(rename = "type")]
+        pub type_: Option<String>,
+        #[serde(default)]
+        pub contract_ids: Vec<String>,
+        #[serde(default)]
+        pub topics: Vec<Vec<String>>,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
     pub struct Event {
+        #[serde(rename = "type")]
         pub type_: String,
         pub ledger: u32,
         pub ledger_closed_at: String,
@@ -39,11 +122,19 @@ pub mod rpc {
         pub id: String,
         pub paging_token: String,
         pub in_successful_contract_call: bool,
-        pub topic: Vec<String>,
-        pub value: serde_json::Value,
+        pub tx_hash: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub topic: Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub value: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub topic_json: Option<Vec<serde_json::Value>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub value_json: Option<serde_json::Value>,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
     pub struct GetEventsResult {
         pub latest_ledger: u32,
         pub events: Vec<Event>,
@@ -51,27 +142,249 @@ pub mod rpc {
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct SendTransactionResult {
+        // One of "PENDING", "DUPLICATE", "TRY_AGAIN_LATER" or "ERROR"
+        pub status: String,
+        pub hash: String,
+        pub latest_ledger: u32,
+        pub latest_ledger_close_time: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub error_result_xdr: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GetTransactionResult {
+        // One of "NOT_FOUND", "SUCCESS" or "FAILED"
+        pub status: String,
+        pub latest_ledger: u32,
+        pub latest_ledger_close_time: String,
+        pub oldest_ledger: u32,
+        pub oldest_ledger_close_time: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub ledger: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub created_at: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub application_order: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub fee_bump: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub envelope_xdr: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub result_xdr: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub result_meta_xdr: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct TransactionInfo {
+        // Either "SUCCESS" or "FAILED"
+        pub status: String,
+        pub application_order: u32,
+        pub fee_bump: bool,
+        pub envelope_xdr: String,
+        pub result_xdr: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub result_meta_xdr: Option<String>,
+        pub ledger: u32,
+        pub created_at: i64,
+        pub tx_hash: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GetTransactionsResult {
+        pub transactions: Vec<TransactionInfo>,
+        pub latest_ledger: u32,
+        pub latest_ledger_close_timestamp: i64,
+        pub oldest_ledger: u32,
+        pub oldest_ledger_close_timestamp: i64,
+        pub cursor: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
     pub struct LedgerEntry {
         pub key: String,
         pub xdr: String,
-        pub last_modified_ledger: u32,
+        pub last_modified_ledger_seq: u32,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
     pub struct GetLedgerEntriesResult {
         pub entries: Vec<LedgerEntry>,
         pub latest_ledger: u32,
     }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct AccountThresholds {
+        pub low_threshold: u8,
+        pub med_threshold: u8,
+        pub high_threshold: u8,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct AccountFlags {
+        pub auth_required: bool,
+        pub auth_revocable: bool,
+        pub auth_immutable: bool,
+        pub auth_clawback_enabled: bool,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct AccountSigner {
+        pub key: String,
+        pub weight: u32,
+        // One of "ed25519_public_key", "sha256_hash", "preauth_tx" or "ed25519_signed_payload"
+        #[serde(rename = "type")]
+        pub type_: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub sponsor: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Balance {
+        // One of "native", "credit_alphanum4", "credit_alphanum12" or "liquidity_pool_shares"
+        pub asset_type: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub asset_code: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub asset_issuer: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub liquidity_pool_id: Option<String>,
+        pub balance: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub limit: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub buying_liabilities: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub selling_liabilities: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub is_authorized: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub is_authorized_to_maintain_liabilities: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub is_clawback_enabled: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub sponsor: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub last_modified_ledger_seq: Option<u32>,
+        // The trustline's `TrustLineEntry`, absent for the native balance
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub entry_xdr: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub entry_json: Option<serde_json::Value>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct Account {
+        pub account_id: String,
+        pub sequence: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub sequence_ledger: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub sequence_time: Option<String>,
+        pub subentry_count: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub home_domain: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub inflation_destination: Option<String>,
+        pub thresholds: AccountThresholds,
+        pub flags: AccountFlags,
+        pub signers: Vec<AccountSigner>,
+        pub balances: Vec<Balance>,
+        pub num_sponsoring: u32,
+        pub num_sponsored: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub sponsor: Option<String>,
+        pub last_modified_ledger_seq: u32,
+        // The account's `AccountEntry`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub entry_xdr: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub entry_json: Option<serde_json::Value>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GetAccountResult {
+        pub account: Account,
+        pub latest_ledger: u32,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GetBalancesResult {
+        pub account_id: String,
+        pub balances: Vec<Balance>,
+        pub latest_ledger: u32,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct VersionInfo {
+        pub version: String,
+        pub commit_hash: String,
+        pub build_timestamp: String,
+        pub captive_core_version: String,
+        pub protocol_version: u32,
+        pub horizon_version: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct FeeDistribution {
+        pub max: String,
+        pub min: String,
+        pub mode: String,
+        pub p10: String,
+        pub p20: String,
+        pub p30: String,
+        pub p40: String,
+        pub p50: String,
+        pub p60: String,
+        pub p70: String,
+        pub p80: String,
+        pub p90: String,
+        pub p95: String,
+        pub p99: String,
+        pub transaction_count: String,
+        pub ledger_count: u32,
+    }
+
+    // Without `sorobanInclusionFee`, since Horizon's charged fees include the
+    // resource fees of Soroban transactions
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GetFeeStatsResult {
+        pub inclusion_fee: FeeDistribution,
+        pub latest_ledger: u32,
+    }
 }
 
 // Horizon API Response Models
+//
+// These mirror Horizon's JSON resources. Fields kept only to match them are
+// marked `allow(dead_code)`.
 pub mod horizon {
+    use std::collections::HashMap;
+
     use super::*;
 
     #[derive(Debug, Deserialize)]
     pub struct Response<T> {
         pub _embedded: Option<Embedded<T>>,
         pub _links: Option<Links>,
+        #[allow(dead_code)]
         #[serde(flatten)]
         pub data: Option<T>,
     }
@@ -83,18 +396,36 @@ pub mod horizon {
 
     #[derive(Debug, Deserialize)]
     pub struct Links {
+        #[allow(dead_code)]
         pub next: Option<Link>,
+        #[allow(dead_code)]
         pub prev: Option<Link>,
+        #[allow(dead_code)]
         pub self_: Option<Link>,
     }
 
     #[derive(Debug, Deserialize)]
     pub struct Link {
+        #[allow(dead_code)]
         pub href: String,
     }
 
+    // Horizon's error responses are RFC 7807 problem documents
+    #[derive(Debug, Deserialize)]
+    pub struct Problem {
+        #[serde(rename = "type")]
+        pub type_: String,
+        #[allow(dead_code)]
+        pub title: String,
+        #[allow(dead_code)]
+        pub status: u16,
+        #[allow(dead_code)]
+        pub detail: Option<String>,
+    }
+
     #[derive(Debug, Deserialize)]
     pub struct LedgerResponse {
+        #[allow(dead_code)]
         pub id: String,
         pub paging_token: String,
         pub hash: String,
@@ -110,6 +441,67 @@ pub mod horizon {
         pub base_reserve_in_stroops: u32,
         pub max_tx_set_size: u32,
         pub protocol_version: u32,
+        pub header_xdr: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct OperationResponse {
+        pub id: String,
+        pub paging_token: String,
+        #[allow(dead_code)]
+        pub transaction_successful: bool,
+        #[allow(dead_code)]
+        pub source_account: String,
+        #[serde(rename = "type")]
+        pub type_: String,
+        #[allow(dead_code)]
+        pub type_i: u32,
+        pub created_at: String,
+        pub transaction_hash: String,
+        // Only present when requested with `join=transactions`
+        pub transaction: Option<TransactionResponse>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct TransactionResponse {
+        #[allow(dead_code)]
+        pub id: String,
+        pub paging_token: String,
+        pub successful: bool,
+        pub hash: String,
+        pub ledger: u32,
+        pub created_at: String,
+        #[allow(dead_code)]
+        pub source_account: String,
+        #[allow(dead_code)]
+        pub fee_account: Option<String>,
+        #[allow(dead_code)]
+        pub fee_charged: String,
+        #[allow(dead_code)]
+        pub max_fee: String,
+        #[allow(dead_code)]
+        pub operation_count: u32,
+        pub envelope_xdr: String,
+        pub result_xdr: String,
+        pub result_meta_xdr: Option<String>,
+        #[allow(dead_code)]
+        pub fee_meta_xdr: Option<String>,
+        // Only present for fee bump transactions
+        pub fee_bump_transaction: Option<FeeBumpTransaction>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct FeeBumpTransaction {
+        #[allow(dead_code)]
+        pub hash: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct AsyncTransactionSubmissionResponse {
+        pub tx_status: String,
+        pub hash: String,
+        #[serde(rename = "errorResultXdr")]
+        pub error_result_xdr: Option<String>,
     }
 
     #[derive(Debug, Deserialize)]
@@ -117,6 +509,7 @@ pub mod horizon {
         pub id: String,
         pub paging_token: String,
         pub account: String,
+        #[serde(rename = "type")]
         pub type_: String,
         pub type_i: u32,
         pub created_at: String,
@@ -125,12 +518,52 @@ pub mod horizon {
 
     #[derive(Debug, Deserialize)]
     pub struct AccountResponse {
+        #[allow(dead_code)]
         pub id: String,
         pub account_id: String,
         pub sequence: String,
+        pub sequence_ledger: Option<u32>,
+        pub sequence_time: Option<String>,
         pub subentry_count: u32,
+        pub inflation_destination: Option<String>,
+        pub home_domain: Option<String>,
+        pub last_modified_ledger: u32,
+        pub thresholds: AccountThresholds,
+        pub flags: AccountFlags,
         pub balances: Vec<Balance>,
-        // Additional fields can be added as needed
+        pub signers: Vec<Signer>,
+        #[serde(default)]
+        pub data: HashMap<String, String>,
+        #[serde(default)]
+        pub num_sponsoring: u32,
+        #[serde(default)]
+        pub num_sponsored: u32,
+        pub sponsor: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct AccountThresholds {
+        pub low_threshold: u8,
+        pub med_threshold: u8,
+        pub high_threshold: u8,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct AccountFlags {
+        pub auth_required: bool,
+        pub auth_revocable: bool,
+        pub auth_immutable: bool,
+        #[serde(default)]
+        pub auth_clawback_enabled: bool,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Signer {
+        pub key: String,
+        pub weight: u32,
+        #[serde(rename = "type")]
+        pub type_: String,
+        pub sponsor: Option<String>,
     }
 
     #[derive(Debug, Deserialize)]
@@ -139,6 +572,104 @@ pub mod horizon {
         pub asset_type: String,
         pub asset_code: Option<String>,
         pub asset_issuer: Option<String>,
+        pub liquidity_pool_id: Option<String>,
+        pub limit: Option<String>,
+        pub buying_liabilities: Option<String>,
+        pub selling_liabilities: Option<String>,
+        pub last_modified_ledger: Option<u32>,
+        pub is_authorized: Option<bool>,
+        pub is_authorized_to_maintain_liabilities: Option<bool>,
+        pub is_clawback_enabled: Option<bool>,
+        pub sponsor: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Asset {
+        pub asset_type: String,
+        pub asset_code: Option<String>,
+        pub asset_issuer: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Price {
+        pub n: i32,
+        pub d: i32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct OfferResponse {
+        pub id: String,
+        #[allow(dead_code)]
+        pub paging_token: String,
+        pub seller: String,
+        pub selling: Asset,
+        pub buying: Asset,
+        pub amount: String,
+        pub price_r: Price,
+        #[allow(dead_code)]
+        pub price: String,
+        pub last_modified_ledger: u32,
+        pub sponsor: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct ClaimableBalanceResponse {
+        #[allow(dead_code)]
+        pub id: String,
+        #[allow(dead_code)]
+        pub paging_token: String,
+        // Either "native" or "CODE:ISSUER"
+        pub asset: String,
+        pub amount: String,
+        pub sponsor: Option<String>,
+        pub last_modified_ledger: u32,
+        pub claimants: Vec<Claimant>,
+        #[serde(default)]
+        pub flags: ClaimableBalanceFlags,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Claimant {
+        pub destination: String,
+        pub predicate: ClaimPredicate,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct ClaimPredicate {
+        #[serde(default)]
+        pub unconditional: bool,
+        pub and: Option<Vec<ClaimPredicate>>,
+        pub or: Option<Vec<ClaimPredicate>>,
+        pub not: Option<Box<ClaimPredicate>>,
+        pub abs_before_epoch: Option<String>,
+        pub rel_before: Option<String>,
+    }
+
+    #[derive(Debug, Default, Deserialize)]
+    pub struct ClaimableBalanceFlags {
+        #[serde(default)]
+        pub clawback_enabled: bool,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct LiquidityPoolResponse {
+        pub id: String,
+        #[allow(dead_code)]
+        pub paging_token: String,
+        pub fee_bp: u32,
+        #[serde(rename = "type")]
+        pub type_: String,
+        pub total_trustlines: String,
+        pub total_shares: String,
+        pub reserves: Vec<Reserve>,
+        pub last_modified_ledger: u32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Reserve {
+        // Either "native" or "CODE:ISSUER"
+        pub asset: String,
+        pub amount: String,
     }
 
     #[derive(Debug, Deserialize)]
@@ -148,6 +679,41 @@ pub mod horizon {
         pub ingest_latest_ledger: u32,
         pub history_latest_ledger: u32,
         pub history_latest_ledger_closed_at: String,
+        pub history_elder_ledger: u32,
         pub network_passphrase: String,
+        pub current_protocol_version: u32,
+        #[allow(dead_code)]
+        pub core_supported_protocol_version: u32,
+    }
+
+    // Horizon reports every fee statistic as a string
+    #[derive(Debug, Deserialize)]
+    pub struct FeeStatsResponse {
+        pub last_ledger: String,
+        #[allow(dead_code)]
+        pub last_ledger_base_fee: String,
+        #[allow(dead_code)]
+        pub ledger_capacity_usage: String,
+        pub fee_charged: FeeDistribution,
+        #[allow(dead_code)]
+        pub max_fee: FeeDistribution,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct FeeDistribution {
+        pub max: String,
+        pub min: String,
+        pub mode: String,
+        pub p10: String,
+        pub p20: String,
+        pub p30: String,
+        pub p40: String,
+        pub p50: String,
+        pub p60: String,
+        pub p70: String,
+        pub p80: String,
+        pub p90: String,
+        pub p95: String,
+        pub p99: String,
     }
 }
\ No newline at end of file
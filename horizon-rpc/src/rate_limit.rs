@@ -0,0 +1,147 @@
+// Token bucket rate limits per client, applied to each JSON-RPC call.
+//
+// Calls that carry one of the configured API keys in the `X-API-Key` header
+// are limited per key, and all other calls per IP address. Each bucket holds
+// a second's worth of calls, so clients may burst up to their rate.
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::{self, Either, Ready};
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::server::MethodResponse;
+use jsonrpsee::types::Request;
+
+use crate::rpc::error;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+// Above this many buckets, full ones are dropped, since a new bucket starts
+// full anyway
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Who made a call, attached to each request by the server.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Client {
+    ApiKey(String),
+    Ip(IpAddr),
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant, rate: f64) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(rate);
+        self.updated = now;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    // Calls per second, unlimited if unset
+    per_ip: Option<NonZeroU32>,
+    per_key: Option<NonZeroU32>,
+    api_keys: HashSet<String>,
+    buckets: Mutex<HashMap<Client, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(
+        per_ip: Option<NonZeroU32>,
+        per_key: Option<NonZeroU32>,
+        api_keys: impl IntoIterator<Item = String>,
+    ) -> Self {
+        Self {
+            per_ip,
+            per_key,
+            api_keys: api_keys.into_iter().collect(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Identifies the client of a request from its address and API key.
+    /// Unknown keys are ignored.
+    pub fn client(&self, ip: IpAddr, api_key: Option<&str>) -> Client {
+        match api_key {
+            Some(key) if self.api_keys.contains(key) => Client::ApiKey(key.to_string()),
+            _ => Client::Ip(ip),
+        }
+    }
+
+    fn rate(&self, client: &Client) -> Option<f64> {
+        let rate = match client {
+            Client::ApiKey(_) => self.per_key,
+            Client::Ip(_) => self.per_ip,
+        };
+        rate.map(|rate| f64::from(rate.get()))
+    }
+
+    /// Takes a token for a call by `client`, or returns how long until one
+    /// is available.
+    pub fn check(&self, client: &Client) -> Result<(), Duration> {
+        let Some(rate) = self.rate(client) else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|client, bucket| {
+                let rate = self.rate(client).unwrap_or_default();
+                bucket.refill(now, rate);
+                bucket.tokens < rate
+            });
+        }
+
+        let bucket = buckets.entry(client.clone()).or_insert(Bucket {
+            tokens: rate,
+            updated: now,
+        });
+        bucket.refill(now, rate);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+/// JSON-RPC middleware that rejects calls over the client's rate limit.
+#[derive(Debug, Clone)]
+pub struct RateLimit<S> {
+    service: S,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S> RateLimit<S> {
+    pub fn new(service: S, limiter: Arc<RateLimiter>) -> Self {
+        Self { service, limiter }
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for RateLimit<S>
+where
+    S: RpcServiceT<'a>,
+{
+    type Future = Either<S::Future, Ready<MethodResponse>>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let checked = request.extensions().get::<Client>().map(|client| self.limiter.check(client));
+        match checked {
+            Some(Err(retry_after)) => {
+                let response = MethodResponse::error(request.id, error::rate_limited(retry_after));
+                Either::Right(future::ready(response))
+            }
+            _ => Either::Left(self.service.call(request)),
+        }
+    }
+}
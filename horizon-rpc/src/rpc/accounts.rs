@@ -0,0 +1,99 @@
+// Converts Horizon accounts into the getAccount and getBalances shapes, with
+// the ledger entries behind them.
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use stellar_xdr::{LedgerEntryData, WriteXdr};
+
+use crate::models::horizon;
+use crate::models::rpc::{Account, AccountFlags, AccountSigner, AccountThresholds, Balance, XdrFormat};
+use crate::rpc::{ledger_entries, xdr};
+
+/// Converts a Horizon account into the RPC `getAccount` shape.
+pub fn account(account: &horizon::AccountResponse, format: XdrFormat) -> Result<Account> {
+    let entry = ledger_entries::account_entry(account)?;
+    let LedgerEntryData::Account(entry) = &entry.data else {
+        bail!("account {} did not build an account entry", account.account_id);
+    };
+    let (entry_xdr, entry_json) = encode(entry, format)?;
+
+    Ok(Account {
+        account_id: account.account_id.clone(),
+        sequence: account.sequence.clone(),
+        sequence_ledger: account.sequence_ledger,
+        sequence_time: account.sequence_time.clone(),
+        subentry_count: account.subentry_count,
+        home_domain: account.home_domain.clone().filter(|domain| !domain.is_empty()),
+        inflation_destination: account.inflation_destination.clone(),
+        thresholds: AccountThresholds {
+            low_threshold: account.thresholds.low_threshold,
+            med_threshold: account.thresholds.med_threshold,
+            high_threshold: account.thresholds.high_threshold,
+        },
+        flags: AccountFlags {
+            auth_required: account.flags.auth_required,
+            auth_revocable: account.flags.auth_revocable,
+            auth_immutable: account.flags.auth_immutable,
+            auth_clawback_enabled: account.flags.auth_clawback_enabled,
+        },
+        signers: account
+            .signers
+            .iter()
+            .map(|signer| AccountSigner {
+                key: signer.key.clone(),
+                weight: signer.weight,
+                type_: signer.type_.clone(),
+                sponsor: signer.sponsor.clone(),
+            })
+            .collect(),
+        balances: balances(account, format)?,
+        num_sponsoring: account.num_sponsoring,
+        num_sponsored: account.num_sponsored,
+        sponsor: account.sponsor.clone(),
+        last_modified_ledger_seq: account.last_modified_ledger,
+        entry_xdr,
+        entry_json,
+    })
+}
+
+/// Converts the balances of a Horizon account into the RPC shape, each
+/// trustline with its `TrustLineEntry`.
+pub fn balances(account: &horizon::AccountResponse, format: XdrFormat) -> Result<Vec<Balance>> {
+    account
+        .balances
+        .iter()
+        .map(|balance| {
+            let entry = ledger_entries::balance_trustline_entry(account, balance)?;
+            let (entry_xdr, entry_json) = match entry.as_ref().map(|entry| &entry.data) {
+                Some(LedgerEntryData::Trustline(entry)) => encode(entry, format)?,
+                _ => (None, None),
+            };
+
+            Ok(Balance {
+                asset_type: balance.asset_type.clone(),
+                asset_code: balance.asset_code.clone(),
+                asset_issuer: balance.asset_issuer.clone(),
+                liquidity_pool_id: balance.liquidity_pool_id.clone(),
+                balance: balance.balance.clone(),
+                limit: balance.limit.clone(),
+                buying_liabilities: balance.buying_liabilities.clone(),
+                selling_liabilities: balance.selling_liabilities.clone(),
+                is_authorized: balance.is_authorized,
+                is_authorized_to_maintain_liabilities: balance.is_authorized_to_maintain_liabilities,
+                is_clawback_enabled: balance.is_clawback_enabled,
+                sponsor: balance.sponsor.clone(),
+                last_modified_ledger_seq: entry.as_ref().map(|entry| entry.last_modified_ledger_seq),
+                entry_xdr,
+                entry_json,
+            })
+        })
+        .collect()
+}
+
+// Encodes an entry as base64 XDR or as JSON, as the client asked
+fn encode<T: WriteXdr + Serialize>(entry: &T, format: XdrFormat) -> Result<(Option<String>, Option<serde_json::Value>)> {
+    match format {
+        XdrFormat::Base64 => Ok((Some(xdr::to_base64(entry)?), None)),
+        XdrFormat::Json => Ok((None, Some(serde_json::to_value(entry)?))),
+    }
+}
@@ -1,11 +1,25 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
 use async_trait::async_trait;
-use jsonrpsee::core::RpcResult;
+use chrono::Utc;
+use futures::{Stream, StreamExt};
+use jsonrpsee::core::{RpcResult, SubscriptionResult};
 use jsonrpsee::proc_macros::rpc;
-use jsonrpsee::types::error::ErrorObject;
-use serde_json::Value;
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage, SubscriptionSink};
+use serde::Serialize;
+use stellar_xdr::{AccountId, Hash, LedgerKey, TransactionEnvelope};
 
 use crate::horizon::HorizonClient;
-use crate::models::rpc::{GetEventsResult, GetLedgerEntriesResult, Health, Ledger, Network};
+use crate::models::horizon::AccountResponse;
+use crate::models::rpc::{
+    Event, EventFilter, GetAccountResult, GetBalancesResult, GetEventsResult, GetFeeStatsResult, GetLedgerEntriesResult, GetLedgersResult, GetTransactionResult,
+    GetTransactionsResult, Health, Ledger, LedgerDetails, LedgerEntry, Network, Pagination,
+    SendTransactionResult, VersionInfo, XdrFormat,
+};
+use crate::rpc::events::{self, EventId};
+use crate::rpc::{accounts, error, fee_stats, ledger_entries, ledgers, transactions, xdr};
 
 #[rpc(server)]
 pub trait StellarRpcApi {
@@ -15,15 +29,27 @@ pub trait StellarRpcApi {
     #[method(name = "getNetwork")]
     async fn get_network(&self) -> RpcResult<Network>;
 
+    #[method(name = "getVersionInfo")]
+    async fn get_version_info(&self) -> RpcResult<VersionInfo>;
+
     #[method(name = "getLatestLedger")]
     async fn get_latest_ledger(&self) -> RpcResult<Ledger>;
 
+    #[method(name = "getLedger")]
+    async fn get_ledger(
+        &self,
+        sequence: Option<u32>,
+        hash: Option<String>,
+        xdr_format: Option<XdrFormat>,
+    ) -> RpcResult<LedgerDetails>;
+
     #[method(name = "getLedgers")]
     async fn get_ledgers(
         &self,
-        cursor: Option<String>,
-        limit: Option<u32>,
-    ) -> RpcResult<Vec<Ledger>>;
+        start_ledger: Option<u32>,
+        pagination: Option<Pagination>,
+        xdr_format: Option<XdrFormat>,
+    ) -> RpcResult<GetLedgersResult>;
 
     #[method(name = "getLedgerEntries")]
     async fn get_ledger_entries(
@@ -31,124 +57,701 @@ pub trait StellarRpcApi {
         keys: Vec<String>,
     ) -> RpcResult<GetLedgerEntriesResult>;
 
+    #[method(name = "getAccount")]
+    async fn get_account(
+        &self,
+        account: String,
+        xdr_format: Option<XdrFormat>,
+    ) -> RpcResult<GetAccountResult>;
+
+    #[method(name = "getBalances")]
+    async fn get_balances(
+        &self,
+        account: String,
+        xdr_format: Option<XdrFormat>,
+    ) -> RpcResult<GetBalancesResult>;
+
     #[method(name = "getEvents")]
     async fn get_events(
         &self,
         start_ledger: Option<u32>,
         end_ledger: Option<u32>,
-        filters: Option<Vec<Value>>,
-        pagination: Option<Value>,
-        xdr_format: Option<String>,
+        filters: Option<Vec<EventFilter>>,
+        pagination: Option<Pagination>,
+        xdr_format: Option<XdrFormat>,
     ) -> RpcResult<GetEventsResult>;
+
+    #[method(name = "sendTransaction")]
+    async fn send_transaction(
+        &self,
+        transaction: String,
+    ) -> RpcResult<SendTransactionResult>;
+
+    #[method(name = "getTransaction")]
+    async fn get_transaction(
+        &self,
+        hash: String,
+    ) -> RpcResult<GetTransactionResult>;
+
+    #[method(name = "getFeeStats")]
+    async fn get_fee_stats(&self) -> RpcResult<GetFeeStatsResult>;
+
+    /// Pushes each ledger as it closes.
+    #[subscription(name = "subscribeLedgers" => "ledger", unsubscribe = "unsubscribeLedgers", item = Ledger)]
+    async fn subscribe_ledgers(&self) -> SubscriptionResult;
+
+    /// Pushes the contract events that pass the filters as their ledgers
+    /// close.
+    #[subscription(name = "subscribeEvents" => "event", unsubscribe = "unsubscribeEvents", item = Event)]
+    async fn subscribe_events(
+        &self,
+        filters: Option<Vec<EventFilter>>,
+        xdr_format: Option<XdrFormat>,
+    ) -> SubscriptionResult;
+
+    #[method(name = "getTransactions")]
+    async fn get_transactions(
+        &self,
+        start_ledger: Option<u32>,
+        pagination: Option<Pagination>,
+    ) -> RpcResult<GetTransactionsResult>;
 }
 
 pub struct StellarRpcServer {
     horizon_client: HorizonClient,
+    friendbot_url: Option<String>,
+    max_ledger_age: Duration,
+    max_ledger_entry_keys: usize,
+    // Caps the `limit` of every paginated method, below their own maximums
+    max_page_limit: Option<u32>,
+}
+
+// Matches the default maximum healthy ledger latency of Stellar RPC
+pub const DEFAULT_MAX_LEDGER_AGE: Duration = Duration::from_secs(30);
+// Matches the default getLedgerEntries key limit of Stellar RPC
+pub const DEFAULT_MAX_LEDGER_ENTRY_KEYS: usize = 200;
+
+// The ledgers Horizon has history for, with their close times in unix seconds
+struct LedgerRange {
+    latest: u32,
+    latest_close_time: i64,
+    oldest: u32,
+    oldest_close_time: i64,
 }
 
 impl StellarRpcServer {
-    pub fn new(horizon_url: String) -> anyhow::Result<Self> {
-        let horizon_client = HorizonClient::new(horizon_url)?;
-        Ok(Self { horizon_client })
+    pub fn new(horizon_client: HorizonClient) -> Self {
+        Self {
+            horizon_client,
+            friendbot_url: None,
+            max_ledger_age: DEFAULT_MAX_LEDGER_AGE,
+            max_ledger_entry_keys: DEFAULT_MAX_LEDGER_ENTRY_KEYS,
+            max_page_limit: None,
+        }
+    }
+
+    /// Sets the friendbot URL reported by `getNetwork`.
+    pub fn with_friendbot_url(mut self, friendbot_url: Option<String>) -> Self {
+        self.friendbot_url = friendbot_url;
+        self
+    }
+
+    /// Sets how long ago the latest ledger may have closed before `getHealth`
+    /// reports the server as unhealthy.
+    pub fn with_max_ledger_age(mut self, max_ledger_age: Duration) -> Self {
+        self.max_ledger_age = max_ledger_age;
+        self
+    }
+
+    /// Sets how many keys a `getLedgerEntries` call may request.
+    pub fn with_max_ledger_entry_keys(mut self, max_ledger_entry_keys: usize) -> Self {
+        self.max_ledger_entry_keys = max_ledger_entry_keys;
+        self
+    }
+
+    /// Sets the largest `limit` paginated methods accept. Methods with a
+    /// lower maximum of their own keep it.
+    pub fn with_max_page_limit(mut self, max_page_limit: Option<u32>) -> Self {
+        self.max_page_limit = max_page_limit;
+        self
+    }
+
+    // The `limit` of a paginated request, checked against the method's
+    // maximum and the configured one
+    fn page_limit(&self, limit: Option<u32>, default: u32, max: u32) -> RpcResult<u32> {
+        let max = self.max_page_limit.map_or(max, |page_limit| page_limit.min(max));
+        let limit = limit.unwrap_or(default.min(max));
+        if limit == 0 || limit > max {
+            return Err(error::invalid_params(format!("Limit must be between 1 and {}", max)));
+        }
+        Ok(limit)
     }
 
-    fn rpc_error(msg: String) -> ErrorObject<'static> {
-        ErrorObject::owned(
-            2001, // Custom error code
-            msg,
-            None::<()>,
-        )
+    // Fetches an account along with the latest ledger, failing if it does not
+    // exist
+    async fn horizon_account(&self, account_id: &str) -> RpcResult<(AccountResponse, u32)> {
+        AccountId::from_str(account_id)
+            .map_err(|e| error::invalid_params(format!("Invalid account {}: {}", account_id, e)))?;
+
+        let root = self.horizon_client.get_root().await
+            .map_err(|e| error::upstream("Failed to get latest ledger", &e))?;
+        let account = self.horizon_client.get_account(account_id).await
+            .map_err(|e| error::upstream("Failed to get account", &e))?
+            .ok_or_else(|| error::not_found(format!("Account {} not found", account_id)))?;
+        Ok((account, root.history_latest_ledger))
+    }
+
+    async fn ledger_range(&self) -> RpcResult<LedgerRange> {
+        let root = self.horizon_client.get_root().await
+            .map_err(|e| error::upstream("Failed to get latest ledger", &e))?;
+        let oldest = self.horizon_client.get_ledger(root.history_elder_ledger).await
+            .map_err(|e| error::upstream("Failed to get oldest ledger", &e))?;
+
+        let timestamp = |time: &str| {
+            transactions::unix_timestamp(time)
+                .map_err(|e| error::internal(format!("Invalid ledger close time: {}", e)))
+        };
+
+        Ok(LedgerRange {
+            latest: root.history_latest_ledger,
+            latest_close_time: timestamp(&root.history_latest_ledger_closed_at)?,
+            oldest: oldest.sequence,
+            oldest_close_time: timestamp(&oldest.closed_at)?,
+        })
+    }
+}
+
+// Sends each item of a stream to a subscriber until either side ends. A
+// failed item ends the subscription with its error.
+async fn forward<T, E>(sink: SubscriptionSink, items: impl Stream<Item = Result<T, E>>) -> SubscriptionResult
+where
+    T: Serialize,
+    E: Display,
+{
+    futures::pin_mut!(items);
+    loop {
+        tokio::select! {
+            _ = sink.closed() => return Ok(()),
+            item = items.next() => match item {
+                Some(item) => {
+                    let item = item.map_err(|e| e.to_string())?;
+                    sink.send(SubscriptionMessage::from_json(&item)?).await?;
+                }
+                None => return Ok(()),
+            },
+        }
     }
 }
 
 #[async_trait]
 impl StellarRpcApiServer for StellarRpcServer {
     async fn get_health(&self) -> RpcResult<Health> {
-        match self.horizon_client.get_root().await {
-            Ok(root) => Ok(Health {
-                status: "healthy".to_string(),
-                horizon_status: format!("Horizon {}, Core {}", root.horizon_version, root.core_version),
-            }),
-            Err(e) => Ok(Health {
-                status: "error".to_string(),
-                horizon_status: format!("Error connecting to Horizon: {}", e),
-            }),
+        let root = self.horizon_client.get_root().await
+            .map_err(|e| error::upstream("Failed to get Horizon status", &e))?;
+
+        let closed_at = transactions::unix_timestamp(&root.history_latest_ledger_closed_at)
+            .map_err(|e| error::internal(format!("Invalid ledger close time: {}", e)))?;
+        let age = Utc::now().timestamp() - closed_at;
+        if age > self.max_ledger_age.as_secs() as i64 {
+            return Err(error::unavailable(format!(
+                "latency ({}s) since last known ledger closed is too high (>{}s)",
+                age,
+                self.max_ledger_age.as_secs()
+            )));
         }
+
+        // A ledger can only be served once Horizon has both ingested it and
+        // written it to its history database
+        let latest_ledger = root.ingest_latest_ledger.min(root.history_latest_ledger);
+        let oldest_ledger = root.history_elder_ledger;
+        Ok(Health {
+            status: "healthy".to_string(),
+            latest_ledger,
+            oldest_ledger,
+            ledger_retention_window: latest_ledger.saturating_sub(oldest_ledger) + 1,
+        })
     }
 
     async fn get_network(&self) -> RpcResult<Network> {
         match self.horizon_client.get_root().await {
             Ok(root) => Ok(Network {
                 passphrase: root.network_passphrase,
-                protocol_version: root.ingest_latest_ledger, // This is an approximation
+                protocol_version: root.current_protocol_version,
+                friendbot_url: self.friendbot_url.clone(),
             }),
-            Err(e) => Err(Self::rpc_error(format!("Failed to get network info: {}", e))),
+            Err(e) => Err(error::upstream("Failed to get network info", &e)),
         }
     }
 
+    async fn get_version_info(&self) -> RpcResult<VersionInfo> {
+        let root = self.horizon_client.get_root().await
+            .map_err(|e| error::upstream("Failed to get version info", &e))?;
+
+        Ok(VersionInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            commit_hash: env!("HORIZON_RPC_COMMIT_HASH").to_string(),
+            build_timestamp: env!("HORIZON_RPC_BUILD_TIMESTAMP").to_string(),
+            captive_core_version: root.core_version,
+            protocol_version: root.current_protocol_version,
+            horizon_version: root.horizon_version,
+        })
+    }
+
     async fn get_latest_ledger(&self) -> RpcResult<Ledger> {
         match self.horizon_client.get_latest_ledger().await {
-            Ok(ledger) => Ok(Ledger {
-                hash: ledger.hash,
-                sequence: ledger.sequence,
-                closed_at: ledger.closed_at,
-                successful_transaction_count: ledger.successful_transaction_count,
-                failed_transaction_count: ledger.failed_transaction_count,
-                operation_count: ledger.operation_count,
-                tx_set_operation_count: ledger.tx_set_operation_count.unwrap_or(0),
-                protocol_version: ledger.protocol_version,
-            }),
-            Err(e) => Err(Self::rpc_error(format!("Failed to get latest ledger: {}", e))),
+            Ok(ledger) => Ok(ledgers::ledger_summary(ledger)),
+            Err(e) => Err(error::upstream("Failed to get latest ledger", &e)),
         }
     }
 
+    async fn get_ledger(
+        &self,
+        sequence: Option<u32>,
+        hash: Option<String>,
+        xdr_format: Option<XdrFormat>,
+    ) -> RpcResult<LedgerDetails> {
+        let ledger = match (sequence, hash) {
+            (Some(sequence), None) => self.horizon_client.get_ledger(sequence).await
+                .map_err(|e| error::upstream(&format!("Failed to get ledger {}", sequence), &e))?,
+            (None, Some(hash)) => {
+                let hash = Hash::from_str(&hash)
+                    .map_err(|e| error::invalid_params(format!("Invalid hash {}: {}", hash, e)))?
+                    .to_string();
+                // Horizon only looks ledgers up by sequence, so a hash is
+                // searched for among the latest page of ledgers. An older
+                // ledger may still exist, so a miss is not reported as not
+                // found
+                let response = self.horizon_client.get_ledgers(None, Some(ledgers::MAX_LIMIT), Some("desc")).await
+                    .map_err(|e| error::upstream("Failed to get ledgers", &e))?;
+                response._embedded.map(|e| e.records).unwrap_or_default()
+                    .into_iter()
+                    .find(|ledger| ledger.hash == hash)
+                    .ok_or_else(|| error::not_implemented(format!(
+                        "Ledger {} is not among the latest {} ledgers, and older ledgers can only be looked up by sequence",
+                        hash,
+                        ledgers::MAX_LIMIT
+                    )))?
+            }
+            (Some(_), Some(_)) => {
+                return Err(error::invalid_params("Only one of sequence and hash may be given".to_string()));
+            }
+            (None, None) => return Err(error::invalid_params("Sequence or hash is required".to_string())),
+        };
+
+        ledgers::ledger_details(ledger, xdr_format.unwrap_or_default())
+            .map_err(|e| error::internal(format!("Failed to convert ledger: {}", e)))
+    }
+
     async fn get_ledgers(
         &self,
-        cursor: Option<String>,
-        limit: Option<u32>,
-    ) -> RpcResult<Vec<Ledger>> {
-        match self.horizon_client.get_ledgers(cursor, limit, Some("desc")).await {
-            Ok(response) => {
-                if let Some(embedded) = response._embedded {
-                    let ledgers = embedded.records.into_iter().map(|l| Ledger {
-                        hash: l.hash,
-                        sequence: l.sequence,
-                        closed_at: l.closed_at,
-                        successful_transaction_count: l.successful_transaction_count,
-                        failed_transaction_count: l.failed_transaction_count,
-                        operation_count: l.operation_count,
-                        tx_set_operation_count: l.tx_set_operation_count.unwrap_or(0),
-                        protocol_version: l.protocol_version,
-                    }).collect();
-                    Ok(ledgers)
-                } else {
-                    Ok(Vec::new())
+        start_ledger: Option<u32>,
+        pagination: Option<Pagination>,
+        xdr_format: Option<XdrFormat>,
+    ) -> RpcResult<GetLedgersResult> {
+        let pagination = pagination.unwrap_or_default();
+        let limit = self.page_limit(pagination.limit, ledgers::DEFAULT_LIMIT, ledgers::MAX_LIMIT)?;
+        let xdr_format = xdr_format.unwrap_or_default();
+
+        let range = self.ledger_range().await?;
+
+        // Cursors are Horizon paging tokens, which are the TOIDs of the ledgers
+        let cursor = match (pagination.cursor, start_ledger) {
+            (Some(cursor), None) => {
+                cursor.parse::<i64>()
+                    .map_err(|e| error::invalid_params(format!("Invalid cursor: {}", e)))?;
+                cursor
+            }
+            (None, Some(start_ledger)) => {
+                if start_ledger < range.oldest || start_ledger > range.latest {
+                    return Err(error::invalid_params(format!(
+                        "Start ledger must be between the oldest ledger {} and the latest ledger {}",
+                        range.oldest, range.latest
+                    )));
                 }
-            },
-            Err(e) => Err(Self::rpc_error(format!("Failed to get ledgers: {}", e))),
-        }
+                ledgers::start_cursor(start_ledger)
+            }
+            (Some(_), Some(_)) => {
+                return Err(error::invalid_params("Start ledger must be omitted when a cursor is given".to_string()));
+            }
+            (None, None) => return Err(error::invalid_params("Start ledger or cursor is required".to_string())),
+        };
+
+        let response = self.horizon_client.get_ledgers(Some(cursor.clone()), Some(limit), Some("asc")).await
+            .map_err(|e| error::upstream("Failed to get ledgers", &e))?;
+        let records = response._embedded.map(|e| e.records).unwrap_or_default();
+
+        let cursor = records.last().map(|l| l.paging_token.clone()).unwrap_or(cursor);
+        let ledgers = records.into_iter().map(|l| ledgers::ledger_info(l, xdr_format))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(|e| error::internal(format!("Invalid ledger: {}", e)))?;
+
+        Ok(GetLedgersResult {
+            ledgers,
+            latest_ledger: range.latest,
+            latest_ledger_close_time: range.latest_close_time,
+            oldest_ledger: range.oldest,
+            oldest_ledger_close_time: range.oldest_close_time,
+            cursor,
+        })
     }
 
     async fn get_ledger_entries(
         &self,
-        _keys: Vec<String>,
+        keys: Vec<String>,
     ) -> RpcResult<GetLedgerEntriesResult> {
-        // This is a placeholder implementation
-        // Horizon doesn't directly support getting arbitrary ledger entries
-        // This would need to be implemented based on the specific entries needed
-        Err(Self::rpc_error("Not implemented".to_string()))
+        if keys.len() > self.max_ledger_entry_keys {
+            return Err(error::invalid_params(format!(
+                "At most {} keys may be requested, got {}",
+                self.max_ledger_entry_keys,
+                keys.len()
+            )));
+        }
+
+        let root = self.horizon_client.get_root().await
+            .map_err(|e| error::upstream("Failed to get latest ledger", &e))?;
+
+        let mut entries = Vec::new();
+        for key in keys {
+            let ledger_key: LedgerKey = xdr::from_base64(&key)
+                .map_err(|e| error::invalid_params(format!("Invalid ledger key {}: {}", key, e)))?;
+            if !ledger_entries::is_available(&ledger_key) {
+                return Err(error::not_implemented(format!(
+                    "{} ledger entries are not available from Horizon",
+                    ledger_key.name()
+                )));
+            }
+
+            let entry = ledger_entries::get_ledger_entry(&self.horizon_client, &ledger_key).await
+                .map_err(|e| error::from_anyhow(&format!("Failed to get ledger entry {}", key), &e))?;
+
+            // Entries that don't exist are left out of the result
+            if let Some(entry) = entry {
+                let xdr = xdr::to_base64(&entry.data)
+                    .map_err(|e| error::internal(format!("Failed to encode ledger entry {}: {}", key, e)))?;
+                entries.push(LedgerEntry {
+                    key,
+                    xdr,
+                    last_modified_ledger_seq: entry.last_modified_ledger_seq,
+                });
+            }
+        }
+
+        Ok(GetLedgerEntriesResult {
+            entries,
+            latest_ledger: root.history_latest_ledger,
+        })
+    }
+
+    async fn get_account(
+        &self,
+        account: String,
+        xdr_format: Option<XdrFormat>,
+    ) -> RpcResult<GetAccountResult> {
+        let (horizon_account, latest_ledger) = self.horizon_account(&account).await?;
+        let account = accounts::account(&horizon_account, xdr_format.unwrap_or_default())
+            .map_err(|e| error::internal(format!("Failed to convert account {}: {}", account, e)))?;
+        Ok(GetAccountResult { account, latest_ledger })
+    }
+
+    async fn get_balances(
+        &self,
+        account: String,
+        xdr_format: Option<XdrFormat>,
+    ) -> RpcResult<GetBalancesResult> {
+        let (horizon_account, latest_ledger) = self.horizon_account(&account).await?;
+        let balances = accounts::balances(&horizon_account, xdr_format.unwrap_or_default())
+            .map_err(|e| error::internal(format!("Failed to convert balances of {}: {}", account, e)))?;
+        Ok(GetBalancesResult {
+            account_id: account,
+            balances,
+            latest_ledger,
+        })
     }
 
     async fn get_events(
         &self,
-        _start_ledger: Option<u32>,
-        _end_ledger: Option<u32>,
-        _filters: Option<Vec<Value>>,
-        _pagination: Option<Value>,
-        _xdr_format: Option<String>,
+        start_ledger: Option<u32>,
+        end_ledger: Option<u32>,
+        filters: Option<Vec<EventFilter>>,
+        pagination: Option<Pagination>,
+        xdr_format: Option<XdrFormat>,
     ) -> RpcResult<GetEventsResult> {
-        // This is a placeholder implementation
-        // Horizon has effects endpoint, but mapping to events needs careful implementation
-        Err(Self::rpc_error("Not implemented".to_string()))
+        let filters = events::parse_filters(&filters.unwrap_or_default())
+            .map_err(|e| error::invalid_params(format!("Invalid filter: {}", e)))?;
+
+        let pagination = pagination.unwrap_or_default();
+        let limit = self.page_limit(pagination.limit, events::DEFAULT_LIMIT, events::MAX_LIMIT)?;
+        let xdr_format = xdr_format.unwrap_or_default();
+
+        let root = self.horizon_client.get_root().await
+            .map_err(|e| error::upstream("Failed to get latest ledger", &e))?;
+        let latest_ledger = root.history_latest_ledger;
+
+        // Events strictly after this position are returned
+        let after = match (pagination.cursor, start_ledger) {
+            (Some(cursor), None) => cursor.parse::<EventId>()
+                .map_err(|e| error::invalid_params(format!("Invalid cursor: {}", e)))?,
+            (None, Some(start_ledger)) => {
+                if start_ledger > latest_ledger {
+                    return Err(error::invalid_params(format!(
+                        "Start ledger {} is after the latest ledger {}", start_ledger, latest_ledger
+                    )));
+                }
+                EventId::ledger_start(start_ledger)
+            }
+            (Some(_), Some(_)) => {
+                return Err(error::invalid_params("Start ledger must be omitted when a cursor is given".to_string()));
+            }
+            (None, None) => return Err(error::invalid_params("Start ledger or cursor is required".to_string())),
+        };
+
+        // The end ledger is exclusive
+        let max_end_ledger = after.ledger().saturating_add(events::MAX_LEDGER_RANGE);
+        let end_ledger = match end_ledger {
+            Some(end_ledger) if end_ledger <= after.ledger() => {
+                return Err(error::invalid_params("End ledger must be after the start ledger".to_string()));
+            }
+            Some(end_ledger) => end_ledger.min(max_end_ledger),
+            None => max_end_ledger,
+        };
+
+        let diagnostic = events::wants_diagnostic(&filters);
+        let mut matched = Vec::new();
+        let mut position = after;
+        let mut horizon_cursor = (after.operation_id - 1).to_string();
+        'pages: for _ in 0..events::MAX_OPERATION_PAGES {
+            let response = self.horizon_client
+                .get_operations(Some(horizon_cursor.clone()), Some(events::OPERATIONS_PAGE_SIZE), Some("asc"), Some("transactions"))
+                .await
+                .map_err(|e| error::upstream("Failed to get operations", &e))?;
+            let operations = response._embedded.map(|e| e.records).unwrap_or_default();
+
+            for operation in &operations {
+                let operation_id: i64 = operation.paging_token.parse()
+                    .map_err(|e| error::internal(format!("Invalid operation paging token: {}", e)))?;
+                if (operation_id >> 32) as u32 >= end_ledger {
+                    position = position.max(EventId::ledger_start(end_ledger));
+                    break 'pages;
+                }
+
+                let operation_events = events::operation_events(operation, diagnostic)
+                    .map_err(|e| error::internal(format!("Failed to read events of operation {}: {}", operation.id, e)))?;
+                for event in operation_events {
+                    if event.id <= after || !events::matches_any(&filters, &event.event) {
+                        continue;
+                    }
+                    matched.push(events::rpc_event(operation, &event, xdr_format)
+                        .map_err(|e| error::internal(format!("Failed to encode event: {}", e)))?);
+                    if matched.len() >= limit as usize {
+                        position = event.id;
+                        break 'pages;
+                    }
+                }
+
+                position = EventId::operation_end(operation_id);
+                horizon_cursor = operation.paging_token.clone();
+            }
+
+            if operations.len() < events::OPERATIONS_PAGE_SIZE as usize {
+                break;
+            }
+        }
+
+        Ok(GetEventsResult {
+            latest_ledger,
+            events: matched,
+            cursor: Some(position.to_string()),
+        })
     }
-}
\ No newline at end of file
+
+    async fn send_transaction(
+        &self,
+        transaction: String,
+    ) -> RpcResult<SendTransactionResult> {
+        xdr::from_base64::<TransactionEnvelope>(&transaction)
+            .map_err(|e| error::invalid_params(format!("Invalid transaction envelope: {}", e)))?;
+
+        // The latest ledger is read first, so that once the transaction is
+        // submitted nothing can fail and have the client resubmit it
+        let root = self.horizon_client.get_root().await
+            .map_err(|e| error::upstream("Failed to get latest ledger", &e))?;
+        let latest_ledger_close_time = transactions::unix_timestamp(&root.history_latest_ledger_closed_at)
+            .map_err(|e| error::internal(format!("Invalid ledger close time: {}", e)))?;
+
+        let submission = self.horizon_client.submit_transaction_async(&transaction).await
+            .map_err(|e| error::upstream("Failed to submit transaction", &e))?;
+
+        Ok(SendTransactionResult {
+            status: submission.tx_status,
+            hash: submission.hash,
+            latest_ledger: root.history_latest_ledger,
+            latest_ledger_close_time: latest_ledger_close_time.to_string(),
+            error_result_xdr: submission.error_result_xdr,
+        })
+    }
+
+    async fn get_transaction(
+        &self,
+        hash: String,
+    ) -> RpcResult<GetTransactionResult> {
+        transactions::validate_hash(&hash)
+            .map_err(|e| error::invalid_params(format!("Invalid hash: {}", e)))?;
+
+        let range = self.ledger_range().await?;
+        let transaction = self.horizon_client.get_transaction(&hash).await
+            .map_err(|e| error::upstream("Failed to get transaction", &e))?;
+
+        let mut result = GetTransactionResult {
+            status: "NOT_FOUND".to_string(),
+            latest_ledger: range.latest,
+            latest_ledger_close_time: range.latest_close_time.to_string(),
+            oldest_ledger: range.oldest,
+            oldest_ledger_close_time: range.oldest_close_time.to_string(),
+            ledger: None,
+            created_at: None,
+            application_order: None,
+            fee_bump: None,
+            envelope_xdr: None,
+            result_xdr: None,
+            result_meta_xdr: None,
+        };
+
+        if let Some(transaction) = transaction {
+            result.status = transactions::status(&transaction).to_string();
+            result.ledger = Some(transaction.ledger);
+            result.created_at = Some(transactions::unix_timestamp(&transaction.created_at)
+                .map_err(|e| error::internal(format!("Invalid transaction close time: {}", e)))?
+                .to_string());
+            result.application_order = Some(transactions::application_order(&transaction)
+                .map_err(|e| error::internal(format!("Invalid transaction paging token: {}", e)))?);
+            result.fee_bump = Some(transaction.fee_bump_transaction.is_some());
+            result.envelope_xdr = Some(transaction.envelope_xdr);
+            result.result_xdr = Some(transaction.result_xdr);
+            result.result_meta_xdr = transaction.result_meta_xdr;
+        }
+
+        Ok(result)
+    }
+
+    async fn get_transactions(
+        &self,
+        start_ledger: Option<u32>,
+        pagination: Option<Pagination>,
+    ) -> RpcResult<GetTransactionsResult> {
+        let pagination = pagination.unwrap_or_default();
+        let limit = self.page_limit(pagination.limit, transactions::DEFAULT_LIMIT, transactions::MAX_LIMIT)?;
+
+        let range = self.ledger_range().await?;
+
+        // Cursors are Horizon paging tokens, which are the TOIDs of the transactions
+        let cursor = match (pagination.cursor, start_ledger) {
+            (Some(cursor), None) => {
+                cursor.parse::<i64>()
+                    .map_err(|e| error::invalid_params(format!("Invalid cursor: {}", e)))?;
+                cursor
+            }
+            (None, Some(start_ledger)) => {
+                if start_ledger < range.oldest || start_ledger > range.latest {
+                    return Err(error::invalid_params(format!(
+                        "Start ledger must be between the oldest ledger {} and the latest ledger {}",
+                        range.oldest, range.latest
+                    )));
+                }
+                (i64::from(start_ledger) << 32).to_string()
+            }
+            (Some(_), Some(_)) => {
+                return Err(error::invalid_params("Start ledger must be omitted when a cursor is given".to_string()));
+            }
+            (None, None) => return Err(error::invalid_params("Start ledger or cursor is required".to_string())),
+        };
+
+        let response = self.horizon_client.get_transactions(Some(cursor.clone()), Some(limit), Some("asc")).await
+            .map_err(|e| error::upstream("Failed to get transactions", &e))?;
+        let records = response._embedded.map(|e| e.records).unwrap_or_default();
+
+        let cursor = records.last().map(|t| t.paging_token.clone()).unwrap_or(cursor);
+        let transactions = records.into_iter().map(transactions::transaction_info)
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(|e| error::internal(format!("Invalid transaction: {}", e)))?;
+
+        Ok(GetTransactionsResult {
+            transactions,
+            latest_ledger: range.latest,
+            latest_ledger_close_timestamp: range.latest_close_time,
+            oldest_ledger: range.oldest,
+            oldest_ledger_close_timestamp: range.oldest_close_time,
+            cursor,
+        })
+    }
+
+    async fn get_fee_stats(&self) -> RpcResult<GetFeeStatsResult> {
+        let stats = self.horizon_client.get_fee_stats().await
+            .map_err(|e| error::upstream("Failed to get fee stats", &e))?;
+        let latest_ledger: u32 = stats.last_ledger.parse()
+            .map_err(|e| error::internal(format!("Invalid latest ledger in fee stats: {}", e)))?;
+
+        // The ledgers the stats were computed from, which may not be the
+        // latest ones by now
+        let ledgers = self.horizon_client
+            .get_ledgers(Some(ledgers::end_cursor(latest_ledger)), Some(fee_stats::LEDGER_COUNT), Some("desc"))
+            .await
+            .map_err(|e| error::upstream("Failed to get ledgers", &e))?;
+        let ledgers = ledgers._embedded.map(|e| e.records).unwrap_or_default();
+
+        Ok(GetFeeStatsResult {
+            inclusion_fee: fee_stats::fee_distribution(&stats.fee_charged, &ledgers),
+            latest_ledger,
+        })
+    }
+
+    async fn subscribe_ledgers(&self, pending: PendingSubscriptionSink) -> SubscriptionResult {
+        let stream = match self.horizon_client.stream_ledgers("now").await {
+            Ok(stream) => stream,
+            Err(e) => {
+                pending.reject(error::upstream("Failed to stream ledgers", &e)).await;
+                return Ok(());
+            }
+        };
+
+        let sink = pending.accept().await?;
+        forward(sink, stream.map(|ledger| ledger.map(ledgers::ledger_summary))).await
+    }
+
+    async fn subscribe_events(
+        &self,
+        pending: PendingSubscriptionSink,
+        filters: Option<Vec<EventFilter>>,
+        xdr_format: Option<XdrFormat>,
+    ) -> SubscriptionResult {
+        let filters = match events::parse_filters(&filters.unwrap_or_default()) {
+            Ok(filters) => filters,
+            Err(e) => {
+                pending.reject(error::invalid_params(format!("Invalid filter: {}", e))).await;
+                return Ok(());
+            }
+        };
+        let xdr_format = xdr_format.unwrap_or_default();
+
+        let stream = match self.horizon_client.stream_operations("now", Some("transactions")).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                pending.reject(error::upstream("Failed to stream operations", &e)).await;
+                return Ok(());
+            }
+        };
+
+        let events = stream.flat_map(move |operation| {
+            let events = operation
+                .map_err(anyhow::Error::from)
+                .and_then(|operation| events::matching_events(&operation, &filters, xdr_format));
+            let events: Vec<anyhow::Result<Event>> = match events {
+                Ok(events) => events.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            };
+            futures::stream::iter(events)
+        });
+
+        let sink = pending.accept().await?;
+        forward(sink, events).await
+    }
+}
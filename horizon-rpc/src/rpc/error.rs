@@ -0,0 +1,112 @@
+// JSON-RPC errors returned by the server.
+//
+// Errors caused by Horizon carry the upstream HTTP status and the `type` of
+// Horizon's problem document in `data`, e.g.
+// `{"status": 404, "type": "https://stellar.org/horizon-errors/not_found"}`.
+
+use std::time::Duration;
+
+use jsonrpsee::types::error::ErrorObject;
+use reqwest::StatusCode;
+use serde_json::{json, Map, Value};
+
+use crate::horizon::error::HorizonError;
+
+pub const INVALID_PARAMS: i32 = -32602;
+pub const INTERNAL_ERROR: i32 = -32603;
+// Codes in the range the JSON-RPC spec reserves for server errors
+pub const UPSTREAM_UNAVAILABLE: i32 = -32001;
+pub const NOT_FOUND: i32 = -32002;
+pub const NOT_IMPLEMENTED: i32 = -32003;
+pub const RATE_LIMITED: i32 = -32004;
+
+pub type Error = ErrorObject<'static>;
+
+/// A parameter could not be decoded or is out of range, or the parameters
+/// do not go together, e.g. both or neither of two mutually exclusive
+/// parameters are given.
+pub fn invalid_params(msg: String) -> Error {
+    ErrorObject::owned(INVALID_PARAMS, msg, None::<()>)
+}
+
+/// A resource the request names does not exist.
+pub fn not_found(msg: String) -> Error {
+    ErrorObject::owned(NOT_FOUND, msg, None::<()>)
+}
+
+/// The request needs data that Horizon does not provide.
+pub fn not_implemented(msg: String) -> Error {
+    ErrorObject::owned(NOT_IMPLEMENTED, msg, None::<()>)
+}
+
+/// The server could not handle data it produced or that Horizon returned.
+pub fn internal(msg: String) -> Error {
+    ErrorObject::owned(INTERNAL_ERROR, msg, None::<()>)
+}
+
+/// The server cannot currently serve requests, e.g. it is too far behind the
+/// network.
+pub fn unavailable(msg: String) -> Error {
+    ErrorObject::owned(UPSTREAM_UNAVAILABLE, msg, None::<()>)
+}
+
+/// The client has made too many calls and may retry after `retry_after`.
+pub fn rate_limited(retry_after: Duration) -> Error {
+    let retry_after = retry_after.as_secs_f64().ceil() as u64;
+    ErrorObject::owned(
+        RATE_LIMITED,
+        "Rate limit exceeded".to_string(),
+        Some(json!({ "retryAfter": retry_after })),
+    )
+}
+
+/// A request to Horizon failed.
+pub fn upstream(context: &str, e: &HorizonError) -> Error {
+    let msg = format!("{}: {}", context, e);
+    let mut data = Map::new();
+
+    let code = match e {
+        HorizonError::Timeout | HorizonError::Request(_) | HorizonError::NoBackendOnNetwork => UPSTREAM_UNAVAILABLE,
+        HorizonError::RateLimited { retry_after } => {
+            data.insert("status".to_string(), json!(StatusCode::TOO_MANY_REQUESTS.as_u16()));
+            if let Some(retry_after) = retry_after {
+                data.insert("retryAfter".to_string(), json!(retry_after.as_secs()));
+            }
+            UPSTREAM_UNAVAILABLE
+        }
+        HorizonError::Status {
+            status,
+            problem_type,
+            ..
+        } => {
+            data.insert("status".to_string(), json!(status.as_u16()));
+            if let Some(problem_type) = problem_type {
+                data.insert("type".to_string(), json!(problem_type));
+            }
+            match *status {
+                StatusCode::NOT_FOUND => NOT_FOUND,
+                status if status.is_client_error() => INVALID_PARAMS,
+                _ => UPSTREAM_UNAVAILABLE,
+            }
+        }
+        HorizonError::Url(_)
+        | HorizonError::NoBackends
+        | HorizonError::WrongNetwork { .. }
+        | HorizonError::InvalidResponse(_) => INTERNAL_ERROR,
+    };
+
+    if data.is_empty() {
+        ErrorObject::owned(code, msg, None::<()>)
+    } else {
+        ErrorObject::owned(code, msg, Some(Value::Object(data)))
+    }
+}
+
+/// An operation that may have failed calling Horizon or afterwards. Horizon
+/// failures are reported as by `upstream`, anything else as internal.
+pub fn from_anyhow(context: &str, e: &anyhow::Error) -> Error {
+    match e.downcast_ref::<HorizonError>() {
+        Some(horizon_error) => upstream(context, horizon_error),
+        None => internal(format!("{}: {}", context, e)),
+    }
+}
@@ -0,0 +1,351 @@
+// Contract events read from the transaction meta of Horizon's
+// invoke_host_function operations.
+//
+// Horizon's contract effects only describe asset balance changes, so the
+// events themselves come from the meta of the joined transaction.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use stellar_xdr::{ContractEvent, ContractEventBody, ContractEventType, ScAddress, ScVal, TransactionMeta};
+
+use crate::models::horizon::OperationResponse;
+use crate::models::rpc::{Event, EventFilter, XdrFormat};
+use crate::rpc::xdr;
+
+pub const DEFAULT_LIMIT: u32 = 100;
+pub const MAX_LIMIT: u32 = 10_000;
+// Maximum number of ledgers scanned by a single request
+pub const MAX_LEDGER_RANGE: u32 = 10_000;
+// Number of operations requested from Horizon per page
+pub const OPERATIONS_PAGE_SIZE: u32 = 200;
+// Maximum number of operation pages requested from Horizon by a single
+// request. A request that reaches it returns the events found so far, with a
+// cursor to resume from
+pub const MAX_OPERATION_PAGES: u32 = 10;
+
+const MAX_FILTERS: usize = 5;
+const MAX_CONTRACT_IDS: usize = 5;
+const MAX_TOPICS: usize = 5;
+const MAX_TOPIC_SEGMENTS: usize = 4;
+
+/// Position of an event: the TOID of the operation that emitted it and the
+/// event's index within that operation. Formatted like Stellar RPC event ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EventId {
+    pub operation_id: i64,
+    pub index: u32,
+}
+
+impl EventId {
+    /// The position just before the first operation of `ledger`.
+    pub fn ledger_start(ledger: u32) -> Self {
+        Self {
+            operation_id: i64::from(ledger) << 32,
+            index: 0,
+        }
+    }
+
+    /// The position just after every event of the given operation.
+    pub fn operation_end(operation_id: i64) -> Self {
+        Self {
+            operation_id,
+            index: u32::MAX,
+        }
+    }
+
+    pub fn ledger(&self) -> u32 {
+        (self.operation_id >> 32) as u32
+    }
+}
+
+impl fmt::Display for EventId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:019}-{:010}", self.operation_id, self.index)
+    }
+}
+
+impl FromStr for EventId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (operation_id, index) = s
+            .split_once('-')
+            .ok_or_else(|| anyhow!("invalid event id: {}", s))?;
+        Ok(Self {
+            operation_id: operation_id.parse()?,
+            index: index.parse()?,
+        })
+    }
+}
+
+enum Segment {
+    // Matches exactly one topic
+    Any,
+    // Matches zero or more trailing topics
+    AnyTrailing,
+    Exact(ScVal),
+}
+
+/// An event filter with its contract ids and topic segments validated.
+pub struct Filter {
+    types: Option<Vec<ContractEventType>>,
+    contract_ids: Vec<String>,
+    topics: Vec<Vec<Segment>>,
+}
+
+impl Filter {
+    pub fn parse(filter: &EventFilter) -> Result<Self> {
+        let types = filter
+            .type_
+            .as_deref()
+            .map(|types| types.split(',').map(|t| event_type(t.trim())).collect::<Result<Vec<_>>>())
+            .transpose()?;
+
+        if filter.contract_ids.len() > MAX_CONTRACT_IDS {
+            bail!("at most {} contract ids are allowed per filter", MAX_CONTRACT_IDS);
+        }
+        for contract_id in &filter.contract_ids {
+            match ScAddress::from_str(contract_id) {
+                Ok(ScAddress::Contract(_)) => {}
+                _ => bail!("invalid contract id: {}", contract_id),
+            }
+        }
+
+        if filter.topics.len() > MAX_TOPICS {
+            bail!("at most {} topic filters are allowed per filter", MAX_TOPICS);
+        }
+        let topics = filter
+            .topics
+            .iter()
+            .map(|topic| {
+                if topic.is_empty() || topic.len() > MAX_TOPIC_SEGMENTS {
+                    bail!("topic filters must have between 1 and {} segments", MAX_TOPIC_SEGMENTS);
+                }
+                topic
+                    .iter()
+                    .enumerate()
+                    .map(|(i, segment)| match segment.as_str() {
+                        "*" => Ok(Segment::Any),
+                        "**" if i == topic.len() - 1 => Ok(Segment::AnyTrailing),
+                        "**" => bail!("\"**\" is only allowed as the last topic segment"),
+                        segment => Ok(Segment::Exact(xdr::from_base64(segment)?)),
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            types,
+            contract_ids: filter.contract_ids.clone(),
+            topics,
+        })
+    }
+
+    fn matches(&self, event: &ContractEvent) -> bool {
+        // Diagnostic events only match filters that ask for them by type
+        match &self.types {
+            Some(types) if !types.contains(&event.type_) => return false,
+            None if event.type_ == ContractEventType::Diagnostic => return false,
+            _ => {}
+        }
+
+        if !self.contract_ids.is_empty() {
+            match contract_id(event) {
+                Some(id) if self.contract_ids.contains(&id) => {}
+                _ => return false,
+            }
+        }
+
+        let ContractEventBody::V0(body) = &event.body;
+        self.topics.is_empty() || self.topics.iter().any(|filter| topic_matches(filter, &body.topics))
+    }
+}
+
+fn topic_matches(filter: &[Segment], topics: &[ScVal]) -> bool {
+    let mut topics = topics.iter();
+    for segment in filter {
+        match segment {
+            Segment::AnyTrailing => return true,
+            Segment::Any => {
+                if topics.next().is_none() {
+                    return false;
+                }
+            }
+            Segment::Exact(expected) => {
+                if topics.next() != Some(expected) {
+                    return false;
+                }
+            }
+        }
+    }
+    topics.next().is_none()
+}
+
+/// Parses the filters of a request.
+pub fn parse_filters(filters: &[EventFilter]) -> Result<Vec<Filter>> {
+    if filters.len() > MAX_FILTERS {
+        bail!("at most {} filters are allowed", MAX_FILTERS);
+    }
+    filters.iter().map(Filter::parse).collect()
+}
+
+/// Whether an event passes any of the filters. No filters matches every event
+/// but diagnostic ones.
+pub fn matches_any(filters: &[Filter], event: &ContractEvent) -> bool {
+    if filters.is_empty() {
+        return event.type_ != ContractEventType::Diagnostic;
+    }
+    filters.iter().any(|filter| filter.matches(event))
+}
+
+/// Whether any of the filters asks for diagnostic events.
+pub fn wants_diagnostic(filters: &[Filter]) -> bool {
+    filters
+        .iter()
+        .any(|filter| filter.types.as_ref().is_some_and(|types| types.contains(&ContractEventType::Diagnostic)))
+}
+
+/// An event emitted by an operation.
+pub struct OperationEvent {
+    pub id: EventId,
+    pub event: ContractEvent,
+    pub in_successful_contract_call: bool,
+}
+
+/// Extracts the events emitted by an operation, including diagnostic events
+/// if `diagnostic` is set. Only invoke_host_function operations emit events,
+/// and they must have their transaction joined.
+pub fn operation_events(operation: &OperationResponse, diagnostic: bool) -> Result<Vec<OperationEvent>> {
+    if operation.type_ != "invoke_host_function" {
+        return Ok(Vec::new());
+    }
+
+    let transaction = operation
+        .transaction
+        .as_ref()
+        .ok_or_else(|| anyhow!("operation {} has no joined transaction", operation.id))?;
+    // Horizon can be configured not to keep transaction meta, which leaves
+    // no events to read
+    let Some(meta) = transaction.result_meta_xdr.as_deref() else {
+        return Ok(Vec::new());
+    };
+    let operation_id: i64 = operation.paging_token.parse()?;
+
+    let (contract_events, diagnostic_events) = match xdr::from_base64::<TransactionMeta>(meta)? {
+        TransactionMeta::V3(meta) => match meta.soroban_meta {
+            Some(soroban_meta) => (soroban_meta.events.into_vec(), soroban_meta.diagnostic_events.into_vec()),
+            None => return Ok(Vec::new()),
+        },
+        // From protocol 23 each operation's events are kept in its own meta,
+        // found by the 1-based operation index in the low bits of its TOID
+        TransactionMeta::V4(meta) => {
+            let operation_meta = ((operation_id & 0xFFF) as usize)
+                .checked_sub(1)
+                .and_then(|index| meta.operations.get(index))
+                .ok_or_else(|| anyhow!("transaction {} has no meta for operation {}", transaction.hash, operation.id))?;
+            (operation_meta.events.to_vec(), meta.diagnostic_events.into_vec())
+        }
+        _ => return Ok(Vec::new()),
+    };
+
+    // Contract and system events are numbered as Stellar RPC numbers them.
+    // Diagnostic events, which only some networks emit and which repeat the
+    // contract events, are numbered after them and only read on request
+    let mut events: Vec<(ContractEvent, bool)> = contract_events
+        .into_iter()
+        .map(|event| (event, transaction.successful))
+        .collect();
+    if diagnostic {
+        events.extend(
+            diagnostic_events
+                .into_iter()
+                .filter(|event| event.event.type_ == ContractEventType::Diagnostic)
+                .map(|event| (event.event, event.in_successful_contract_call)),
+        );
+    }
+
+    Ok(events
+        .into_iter()
+        .enumerate()
+        .map(|(index, (event, in_successful_contract_call))| OperationEvent {
+            id: EventId {
+                operation_id,
+                index: index as u32,
+            },
+            event,
+            in_successful_contract_call,
+        })
+        .collect())
+}
+
+/// The events of an operation that pass the filters, in the RPC response
+/// shape.
+pub fn matching_events(operation: &OperationResponse, filters: &[Filter], format: XdrFormat) -> Result<Vec<Event>> {
+    operation_events(operation, wants_diagnostic(filters))?
+        .iter()
+        .filter(|event| matches_any(filters, &event.event))
+        .map(|event| rpc_event(operation, event, format))
+        .collect()
+}
+
+/// Renders an operation's event in the RPC response shape.
+pub fn rpc_event(operation: &OperationResponse, event: &OperationEvent, format: XdrFormat) -> Result<Event> {
+    let ContractEventBody::V0(body) = &event.event.body;
+
+    let (topic, value, topic_json, value_json) = match format {
+        XdrFormat::Base64 => (
+            Some(body.topics.iter().map(xdr::to_base64).collect::<Result<Vec<_>>>()?),
+            Some(xdr::to_base64(&body.data)?),
+            None,
+            None,
+        ),
+        XdrFormat::Json => (
+            None,
+            None,
+            Some(body.topics.iter().map(serde_json::to_value).collect::<Result<Vec<_>, _>>()?),
+            Some(serde_json::to_value(&body.data)?),
+        ),
+    };
+
+    let id = event.id.to_string();
+    Ok(Event {
+        type_: event_type_name(event.event.type_).to_string(),
+        ledger: event.id.ledger(),
+        ledger_closed_at: operation.created_at.clone(),
+        contract_id: contract_id(&event.event).unwrap_or_default(),
+        paging_token: id.clone(),
+        id,
+        in_successful_contract_call: event.in_successful_contract_call,
+        tx_hash: operation.transaction_hash.clone(),
+        topic,
+        value,
+        topic_json,
+        value_json,
+    })
+}
+
+fn contract_id(event: &ContractEvent) -> Option<String> {
+    event
+        .contract_id
+        .as_ref()
+        .map(|hash| ScAddress::Contract(hash.clone()).to_string())
+}
+
+fn event_type(name: &str) -> Result<ContractEventType> {
+    match name {
+        "system" => Ok(ContractEventType::System),
+        "contract" => Ok(ContractEventType::Contract),
+        "diagnostic" => Ok(ContractEventType::Diagnostic),
+        _ => bail!("invalid event type: {}", name),
+    }
+}
+
+fn event_type_name(event_type: ContractEventType) -> &'static str {
+    match event_type {
+        ContractEventType::System => "system",
+        ContractEventType::Contract => "contract",
+        ContractEventType::Diagnostic => "diagnostic",
+    }
+}
@@ -0,0 +1,35 @@
+// Inclusion fee statistics derived from Horizon's fee_stats endpoint.
+
+use crate::models::horizon::{self, LedgerResponse};
+use crate::models::rpc::FeeDistribution;
+
+// Horizon computes its fee statistics over the most recent 5 ledgers
+pub const LEDGER_COUNT: u32 = 5;
+
+/// Converts Horizon's per-operation charged fee distribution into the RPC
+/// shape, counting the transactions in the ledgers it was computed from.
+pub fn fee_distribution(fees: &horizon::FeeDistribution, ledgers: &[LedgerResponse]) -> FeeDistribution {
+    let transaction_count: u32 = ledgers
+        .iter()
+        .map(|ledger| ledger.successful_transaction_count + ledger.failed_transaction_count)
+        .sum();
+
+    FeeDistribution {
+        max: fees.max.clone(),
+        min: fees.min.clone(),
+        mode: fees.mode.clone(),
+        p10: fees.p10.clone(),
+        p20: fees.p20.clone(),
+        p30: fees.p30.clone(),
+        p40: fees.p40.clone(),
+        p50: fees.p50.clone(),
+        p60: fees.p60.clone(),
+        p70: fees.p70.clone(),
+        p80: fees.p80.clone(),
+        p90: fees.p90.clone(),
+        p95: fees.p95.clone(),
+        p99: fees.p99.clone(),
+        transaction_count: transaction_count.to_string(),
+        ledger_count: ledgers.len() as u32,
+    }
+}
@@ -0,0 +1,462 @@
+// Maps ledger keys onto the Horizon resources that hold the same state and
+// rebuilds the ledger entries from Horizon's JSON.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use stellar_xdr::{
+    AccountEntry, AccountEntryExt, AccountEntryExtensionV1, AccountEntryExtensionV1Ext,
+    AccountEntryExtensionV2, AccountEntryExtensionV2Ext, AccountEntryExtensionV3, AccountFlags,
+    AccountId, AlphaNum12, AlphaNum4, Asset, AssetCode, ClaimPredicate, ClaimableBalanceEntry,
+    ClaimableBalanceEntryExt, ClaimableBalanceEntryExtensionV1,
+    ClaimableBalanceEntryExtensionV1Ext, ClaimableBalanceFlags, ClaimableBalanceId, Claimant,
+    ClaimantV0, DataEntry, DataEntryExt, DataValue, ExtensionPoint, Hash, LedgerEntry, LedgerEntryData,
+    LedgerEntryExt, LedgerEntryExtensionV1, LedgerEntryExtensionV1Ext, LedgerKey, Liabilities,
+    LiquidityPoolConstantProductParameters, LiquidityPoolEntry, LiquidityPoolEntryBody,
+    LiquidityPoolEntryConstantProduct, OfferEntry, OfferEntryExt, PoolId, Price, SequenceNumber,
+    Signer, SignerKey, SponsorshipDescriptor, String32, String64, Thresholds, TimePoint,
+    TrustLineAsset, TrustLineEntry, TrustLineEntryExt, TrustLineEntryV1, TrustLineEntryV1Ext,
+    TrustLineFlags,
+};
+
+use crate::horizon::HorizonClient;
+use crate::models::horizon;
+
+// Horizon renders amounts as decimals with seven digits after the point
+const STROOPS_PER_UNIT: i64 = 10_000_000;
+
+/// Whether Horizon exposes the kind of ledger entry `key` refers to.
+pub fn is_available(key: &LedgerKey) -> bool {
+    matches!(
+        key,
+        LedgerKey::Account(_)
+            | LedgerKey::Trustline(_)
+            | LedgerKey::Offer(_)
+            | LedgerKey::Data(_)
+            | LedgerKey::ClaimableBalance(_)
+            | LedgerKey::LiquidityPool(_)
+    )
+}
+
+/// Fetches the current value of `key` from Horizon.
+///
+/// Returns `None` when the entry does not exist. Only classic entries are
+/// available from Horizon; Soroban keys result in an error.
+pub async fn get_ledger_entry(client: &HorizonClient, key: &LedgerKey) -> Result<Option<LedgerEntry>> {
+    match key {
+        LedgerKey::Account(key) => {
+            let account = client.get_account(&key.account_id.to_string()).await?;
+            account.as_ref().map(account_entry).transpose()
+        }
+        LedgerKey::Trustline(key) => {
+            let Some(account) = client.get_account(&key.account_id.to_string()).await? else {
+                return Ok(None);
+            };
+            account
+                .balances
+                .iter()
+                .find(|balance| trustline_matches(balance, &key.asset))
+                .map(|balance| trustline_entry(&account, &key.asset, balance))
+                .transpose()
+        }
+        LedgerKey::Offer(key) => {
+            let offer = client.get_offer(key.offer_id).await?;
+            // Offer ids are unique on their own, but the key also names the seller
+            offer
+                .filter(|offer| offer.seller == key.seller_id.to_string())
+                .as_ref()
+                .map(offer_entry)
+                .transpose()
+        }
+        LedgerKey::Data(key) => {
+            let Some(account) = client.get_account(&key.account_id.to_string()).await? else {
+                return Ok(None);
+            };
+            let name = key.data_name.0.to_utf8_string()?;
+            account
+                .data
+                .get(&name)
+                .map(|value| data_entry(&account, &name, value))
+                .transpose()
+        }
+        LedgerKey::ClaimableBalance(key) => {
+            let ClaimableBalanceId::ClaimableBalanceIdTypeV0(hash) = &key.balance_id;
+            // Horizon prefixes the balance id with its 4 byte type discriminant
+            let balance_id = format!("00000000{}", hash);
+            let balance = client.get_claimable_balance(&balance_id).await?;
+            balance
+                .map(|balance| claimable_balance_entry(&key.balance_id, &balance))
+                .transpose()
+        }
+        LedgerKey::LiquidityPool(key) => {
+            let pool = client.get_liquidity_pool(&key.liquidity_pool_id.0.to_string()).await?;
+            pool.map(|pool| liquidity_pool_entry(&key.liquidity_pool_id, &pool))
+                .transpose()
+        }
+        _ => bail!("{} ledger entries are not available from Horizon", key.name()),
+    }
+}
+
+/// Builds an `AccountEntry` ledger entry from a Horizon account.
+pub fn account_entry(account: &horizon::AccountResponse) -> Result<LedgerEntry> {
+    let account_id = AccountId::from_str(&account.account_id)?;
+    let native = account
+        .balances
+        .iter()
+        .find(|balance| balance.asset_type == "native")
+        .ok_or_else(|| anyhow!("account {} has no native balance", account.account_id))?;
+
+    // Horizon lists the master key alongside the other signers, while the
+    // ledger keeps its weight in the thresholds
+    let mut master_weight = 0;
+    let mut signers = Vec::new();
+    for signer in &account.signers {
+        if signer.key == account.account_id {
+            master_weight = signer.weight;
+            continue;
+        }
+        let key = SignerKey::from_str(&signer.key)?;
+        let sponsor = signer.sponsor.as_deref().map(AccountId::from_str).transpose()?;
+        signers.push((Signer { key, weight: signer.weight }, SponsorshipDescriptor(sponsor)));
+    }
+    signers.sort_by(|(a, _), (b, _)| a.key.cmp(&b.key));
+    let (signers, signer_sponsoring_ids): (Vec<_>, Vec<_>) = signers.into_iter().unzip();
+
+    let mut flags = 0;
+    if account.flags.auth_required {
+        flags |= AccountFlags::RequiredFlag as u32;
+    }
+    if account.flags.auth_revocable {
+        flags |= AccountFlags::RevocableFlag as u32;
+    }
+    if account.flags.auth_immutable {
+        flags |= AccountFlags::ImmutableFlag as u32;
+    }
+    if account.flags.auth_clawback_enabled {
+        flags |= AccountFlags::ClawbackEnabledFlag as u32;
+    }
+
+    let ext_v3 = match account.sequence_ledger {
+        Some(seq_ledger) => AccountEntryExtensionV2Ext::V3(AccountEntryExtensionV3 {
+            ext: ExtensionPoint::V0,
+            seq_ledger,
+            seq_time: TimePoint(account.sequence_time.as_deref().unwrap_or("0").parse()?),
+        }),
+        None => AccountEntryExtensionV2Ext::V0,
+    };
+
+    let entry = AccountEntry {
+        account_id,
+        balance: parse_amount(&native.balance)?,
+        seq_num: SequenceNumber(account.sequence.parse()?),
+        num_sub_entries: account.subentry_count,
+        inflation_dest: account
+            .inflation_destination
+            .as_deref()
+            .map(AccountId::from_str)
+            .transpose()?,
+        flags,
+        home_domain: String32(account.home_domain.as_deref().unwrap_or_default().try_into()?),
+        thresholds: Thresholds([
+            u8::try_from(master_weight)?,
+            account.thresholds.low_threshold,
+            account.thresholds.med_threshold,
+            account.thresholds.high_threshold,
+        ]),
+        signers: signers.try_into()?,
+        ext: AccountEntryExt::V1(AccountEntryExtensionV1 {
+            liabilities: liabilities(native)?,
+            ext: AccountEntryExtensionV1Ext::V2(AccountEntryExtensionV2 {
+                num_sponsored: account.num_sponsored,
+                num_sponsoring: account.num_sponsoring,
+                signer_sponsoring_i_ds: signer_sponsoring_ids.try_into()?,
+                ext: ext_v3,
+            }),
+        }),
+    };
+
+    ledger_entry(
+        account.last_modified_ledger,
+        LedgerEntryData::Account(entry),
+        account.sponsor.as_deref(),
+    )
+}
+
+fn trustline_matches(balance: &horizon::Balance, asset: &TrustLineAsset) -> bool {
+    let credit_matches = |asset_type: &str, code: String, issuer: &AccountId| {
+        balance.asset_type == asset_type
+            && balance.asset_code.as_deref() == Some(code.as_str())
+            && balance.asset_issuer.as_deref() == Some(issuer.to_string().as_str())
+    };
+    match asset {
+        TrustLineAsset::Native => false,
+        TrustLineAsset::CreditAlphanum4(asset) => {
+            credit_matches("credit_alphanum4", asset.asset_code.to_string(), &asset.issuer)
+        }
+        TrustLineAsset::CreditAlphanum12(asset) => {
+            credit_matches("credit_alphanum12", asset.asset_code.to_string(), &asset.issuer)
+        }
+        TrustLineAsset::PoolShare(pool_id) => {
+            balance.liquidity_pool_id.as_deref() == Some(pool_id.0.to_string().as_str())
+        }
+    }
+}
+
+/// Builds the `TrustLine` ledger entry behind one of an account's balances,
+/// or `None` for its native balance, which is part of the account entry.
+pub fn balance_trustline_entry(
+    account: &horizon::AccountResponse,
+    balance: &horizon::Balance,
+) -> Result<Option<LedgerEntry>> {
+    let asset = match (balance.asset_type.as_str(), &balance.asset_code, &balance.asset_issuer) {
+        ("native", _, _) => return Ok(None),
+        ("liquidity_pool_shares", _, _) => {
+            let pool_id = balance
+                .liquidity_pool_id
+                .as_deref()
+                .ok_or_else(|| anyhow!("pool share balance has no liquidity pool id"))?;
+            TrustLineAsset::PoolShare(PoolId(Hash::from_str(pool_id)?))
+        }
+        (_, Some(code), Some(issuer)) => match credit_asset(code, issuer)? {
+            Asset::CreditAlphanum4(asset) => TrustLineAsset::CreditAlphanum4(asset),
+            Asset::CreditAlphanum12(asset) => TrustLineAsset::CreditAlphanum12(asset),
+            Asset::Native => bail!("invalid balance asset: {}", balance.asset_type),
+        },
+        _ => bail!("invalid balance asset: {}", balance.asset_type),
+    };
+    trustline_entry(account, &asset, balance).map(Some)
+}
+
+fn trustline_entry(
+    account: &horizon::AccountResponse,
+    asset: &TrustLineAsset,
+    balance: &horizon::Balance,
+) -> Result<LedgerEntry> {
+    let mut flags = 0;
+    if balance.is_authorized.unwrap_or(false) {
+        flags |= TrustLineFlags::AuthorizedFlag as u32;
+    }
+    if balance.is_authorized_to_maintain_liabilities.unwrap_or(false) {
+        flags |= TrustLineFlags::AuthorizedToMaintainLiabilitiesFlag as u32;
+    }
+    if balance.is_clawback_enabled.unwrap_or(false) {
+        flags |= TrustLineFlags::TrustlineClawbackEnabledFlag as u32;
+    }
+
+    let ext = match asset {
+        // Pool share trustlines never carry liabilities
+        TrustLineAsset::PoolShare(_) => TrustLineEntryExt::V0,
+        _ => TrustLineEntryExt::V1(TrustLineEntryV1 {
+            liabilities: liabilities(balance)?,
+            ext: TrustLineEntryV1Ext::V0,
+        }),
+    };
+
+    let limit = balance
+        .limit
+        .as_deref()
+        .ok_or_else(|| anyhow!("trustline balance has no limit"))?;
+
+    let entry = TrustLineEntry {
+        account_id: AccountId::from_str(&account.account_id)?,
+        asset: asset.clone(),
+        balance: parse_amount(&balance.balance)?,
+        limit: parse_amount(limit)?,
+        flags,
+        ext,
+    };
+
+    ledger_entry(
+        balance.last_modified_ledger.unwrap_or(account.last_modified_ledger),
+        LedgerEntryData::Trustline(entry),
+        balance.sponsor.as_deref(),
+    )
+}
+
+fn offer_entry(offer: &horizon::OfferResponse) -> Result<LedgerEntry> {
+    // Horizon does not expose whether an offer is passive, so flags are left empty
+    let entry = OfferEntry {
+        seller_id: AccountId::from_str(&offer.seller)?,
+        offer_id: offer.id.parse()?,
+        selling: horizon_asset(&offer.selling)?,
+        buying: horizon_asset(&offer.buying)?,
+        amount: parse_amount(&offer.amount)?,
+        price: Price {
+            n: offer.price_r.n,
+            d: offer.price_r.d,
+        },
+        flags: 0,
+        ext: OfferEntryExt::V0,
+    };
+
+    ledger_entry(
+        offer.last_modified_ledger,
+        LedgerEntryData::Offer(entry),
+        offer.sponsor.as_deref(),
+    )
+}
+
+fn data_entry(account: &horizon::AccountResponse, name: &str, value: &str) -> Result<LedgerEntry> {
+    let entry = DataEntry {
+        account_id: AccountId::from_str(&account.account_id)?,
+        data_name: String64(name.try_into()?),
+        data_value: DataValue(STANDARD.decode(value)?.try_into()?),
+        ext: DataEntryExt::V0,
+    };
+
+    // Horizon does not track when individual data entries change, so the
+    // account's last modification is the closest available value
+    ledger_entry(account.last_modified_ledger, LedgerEntryData::Data(entry), None)
+}
+
+fn claimable_balance_entry(
+    balance_id: &ClaimableBalanceId,
+    balance: &horizon::ClaimableBalanceResponse,
+) -> Result<LedgerEntry> {
+    let claimants = balance
+        .claimants
+        .iter()
+        .map(|claimant| {
+            Ok(Claimant::ClaimantTypeV0(ClaimantV0 {
+                destination: AccountId::from_str(&claimant.destination)?,
+                predicate: claim_predicate(&claimant.predicate)?,
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let ext = if balance.flags.clawback_enabled {
+        ClaimableBalanceEntryExt::V1(ClaimableBalanceEntryExtensionV1 {
+            ext: ClaimableBalanceEntryExtensionV1Ext::V0,
+            flags: ClaimableBalanceFlags::ClaimableBalanceClawbackEnabledFlag as u32,
+        })
+    } else {
+        ClaimableBalanceEntryExt::V0
+    };
+
+    let entry = ClaimableBalanceEntry {
+        balance_id: balance_id.clone(),
+        claimants: claimants.try_into()?,
+        asset: parse_asset(&balance.asset)?,
+        amount: parse_amount(&balance.amount)?,
+        ext,
+    };
+
+    ledger_entry(
+        balance.last_modified_ledger,
+        LedgerEntryData::ClaimableBalance(entry),
+        balance.sponsor.as_deref(),
+    )
+}
+
+fn claim_predicate(predicate: &horizon::ClaimPredicate) -> Result<ClaimPredicate> {
+    let predicates = |predicates: &[horizon::ClaimPredicate]| {
+        predicates.iter().map(claim_predicate).collect::<Result<Vec<_>>>()
+    };
+
+    if predicate.unconditional {
+        Ok(ClaimPredicate::Unconditional)
+    } else if let Some(and) = &predicate.and {
+        Ok(ClaimPredicate::And(predicates(and)?.try_into()?))
+    } else if let Some(or) = &predicate.or {
+        Ok(ClaimPredicate::Or(predicates(or)?.try_into()?))
+    } else if let Some(not) = &predicate.not {
+        Ok(ClaimPredicate::Not(Some(Box::new(claim_predicate(not)?))))
+    } else if let Some(epoch) = &predicate.abs_before_epoch {
+        Ok(ClaimPredicate::BeforeAbsoluteTime(epoch.parse()?))
+    } else if let Some(seconds) = &predicate.rel_before {
+        Ok(ClaimPredicate::BeforeRelativeTime(seconds.parse()?))
+    } else {
+        bail!("unrecognized claim predicate: {:?}", predicate)
+    }
+}
+
+fn liquidity_pool_entry(pool_id: &PoolId, pool: &horizon::LiquidityPoolResponse) -> Result<LedgerEntry> {
+    if pool.type_ != "constant_product" {
+        bail!("unsupported liquidity pool type: {}", pool.type_);
+    }
+    let [reserve_a, reserve_b] = pool.reserves.as_slice() else {
+        bail!("liquidity pool {} does not have two reserves", pool.id);
+    };
+
+    let entry = LiquidityPoolEntry {
+        liquidity_pool_id: pool_id.clone(),
+        body: LiquidityPoolEntryBody::LiquidityPoolConstantProduct(LiquidityPoolEntryConstantProduct {
+            params: LiquidityPoolConstantProductParameters {
+                asset_a: parse_asset(&reserve_a.asset)?,
+                asset_b: parse_asset(&reserve_b.asset)?,
+                fee: pool.fee_bp.try_into()?,
+            },
+            reserve_a: parse_amount(&reserve_a.amount)?,
+            reserve_b: parse_amount(&reserve_b.amount)?,
+            total_pool_shares: parse_amount(&pool.total_shares)?,
+            pool_shares_trust_line_count: pool.total_trustlines.parse()?,
+        }),
+    };
+
+    ledger_entry(pool.last_modified_ledger, LedgerEntryData::LiquidityPool(entry), None)
+}
+
+fn ledger_entry(last_modified_ledger_seq: u32, data: LedgerEntryData, sponsor: Option<&str>) -> Result<LedgerEntry> {
+    let ext = match sponsor {
+        Some(sponsor) => LedgerEntryExt::V1(LedgerEntryExtensionV1 {
+            sponsoring_id: SponsorshipDescriptor(Some(AccountId::from_str(sponsor)?)),
+            ext: LedgerEntryExtensionV1Ext::V0,
+        }),
+        None => LedgerEntryExt::V0,
+    };
+    Ok(LedgerEntry {
+        last_modified_ledger_seq,
+        data,
+        ext,
+    })
+}
+
+fn liabilities(balance: &horizon::Balance) -> Result<Liabilities> {
+    let amount = |amount: &Option<String>| amount.as_deref().map(parse_amount).unwrap_or(Ok(0));
+    Ok(Liabilities {
+        buying: amount(&balance.buying_liabilities)?,
+        selling: amount(&balance.selling_liabilities)?,
+    })
+}
+
+/// Parses a Horizon decimal amount such as "100.0000000" into stroops.
+pub fn parse_amount(amount: &str) -> Result<i64> {
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    if fraction.len() > 7 {
+        bail!("amount has more than 7 decimal places: {}", amount);
+    }
+    let whole: i64 = whole.parse()?;
+    let fraction: i64 = format!("{:0<7}", fraction).parse()?;
+    whole
+        .checked_mul(STROOPS_PER_UNIT)
+        .and_then(|stroops| stroops.checked_add(fraction))
+        .ok_or_else(|| anyhow!("amount out of range: {}", amount))
+}
+
+/// Parses an asset in Horizon's "native" or "CODE:ISSUER" notation.
+pub fn parse_asset(asset: &str) -> Result<Asset> {
+    if asset == "native" {
+        return Ok(Asset::Native);
+    }
+    let (code, issuer) = asset
+        .split_once(':')
+        .ok_or_else(|| anyhow!("invalid asset: {}", asset))?;
+    credit_asset(code, issuer)
+}
+
+fn horizon_asset(asset: &horizon::Asset) -> Result<Asset> {
+    match (asset.asset_type.as_str(), &asset.asset_code, &asset.asset_issuer) {
+        ("native", _, _) => Ok(Asset::Native),
+        (_, Some(code), Some(issuer)) => credit_asset(code, issuer),
+        _ => bail!("invalid asset: {:?}", asset),
+    }
+}
+
+fn credit_asset(code: &str, issuer: &str) -> Result<Asset> {
+    let issuer = AccountId::from_str(issuer)?;
+    match AssetCode::from_str(code)? {
+        AssetCode::CreditAlphanum4(asset_code) => Ok(Asset::CreditAlphanum4(AlphaNum4 { asset_code, issuer })),
+        AssetCode::CreditAlphanum12(asset_code) => Ok(Asset::CreditAlphanum12(AlphaNum12 { asset_code, issuer })),
+    }
+}
@@ -0,0 +1,92 @@
+// Helpers for presenting Horizon ledgers in the RPC `getLedger`,
+// `getLedgers` and `getLatestLedger` shapes.
+
+use std::str::FromStr;
+
+use anyhow::Result;
+use stellar_xdr::{Hash, LedgerHeader, LedgerHeaderHistoryEntry, LedgerHeaderHistoryEntryExt};
+
+use crate::models::horizon::LedgerResponse;
+use crate::models::rpc::{Ledger, LedgerDetails, LedgerInfo, XdrFormat};
+use crate::rpc::{transactions, xdr};
+
+pub const DEFAULT_LIMIT: u32 = 50;
+// Horizon's maximum page size
+pub const MAX_LIMIT: u32 = 200;
+
+/// The Horizon cursor that pages from `ledger` onwards. Ledger paging tokens
+/// are TOIDs and Horizon returns the records after the cursor.
+pub fn start_cursor(ledger: u32) -> String {
+    ((i64::from(ledger) << 32) - 1).to_string()
+}
+
+/// The Horizon cursor that pages back from `ledger` in descending order,
+/// where Horizon returns the records before the cursor.
+pub fn end_cursor(ledger: u32) -> String {
+    ((i64::from(ledger) + 1) << 32).to_string()
+}
+
+/// Converts a Horizon ledger into the summary returned by `getLatestLedger`
+/// and pushed to `subscribeLedgers` subscribers.
+pub fn ledger_summary(ledger: LedgerResponse) -> Ledger {
+    Ledger {
+        hash: ledger.hash,
+        sequence: ledger.sequence,
+        closed_at: ledger.closed_at,
+        successful_transaction_count: ledger.successful_transaction_count,
+        failed_transaction_count: ledger.failed_transaction_count,
+        operation_count: ledger.operation_count,
+        tx_set_operation_count: ledger.tx_set_operation_count.unwrap_or(0),
+        protocol_version: ledger.protocol_version,
+    }
+}
+
+/// Converts a Horizon ledger into the RPC `getLedger` shape.
+pub fn ledger_details(ledger: LedgerResponse, format: XdrFormat) -> Result<LedgerDetails> {
+    let (header_xdr, header_json) = header(&ledger, format)?;
+    Ok(LedgerDetails {
+        hash: ledger.hash,
+        sequence: ledger.sequence,
+        closed_at: ledger.closed_at,
+        successful_transaction_count: ledger.successful_transaction_count,
+        failed_transaction_count: ledger.failed_transaction_count,
+        operation_count: ledger.operation_count,
+        tx_set_operation_count: ledger.tx_set_operation_count.unwrap_or(0),
+        protocol_version: ledger.protocol_version,
+        total_coins: ledger.total_coins,
+        fee_pool: ledger.fee_pool,
+        base_fee_in_stroops: ledger.base_fee_in_stroops,
+        base_reserve_in_stroops: ledger.base_reserve_in_stroops,
+        max_tx_set_size: ledger.max_tx_set_size,
+        header_xdr,
+        header_json,
+    })
+}
+
+/// Converts a Horizon ledger into the RPC `getLedgers` shape.
+pub fn ledger_info(ledger: LedgerResponse, format: XdrFormat) -> Result<LedgerInfo> {
+    let (header_xdr, header_json) = header(&ledger, format)?;
+
+    Ok(LedgerInfo {
+        ledger_close_time: transactions::unix_timestamp(&ledger.closed_at)?.to_string(),
+        hash: ledger.hash,
+        sequence: ledger.sequence,
+        header_xdr,
+        header_json,
+    })
+}
+
+// The ledger header as base64 XDR or as JSON, as the client asked. Horizon
+// serves the bare LedgerHeader, which Stellar RPC wraps in a
+// LedgerHeaderHistoryEntry with the ledger's hash
+fn header(ledger: &LedgerResponse, format: XdrFormat) -> Result<(Option<String>, Option<serde_json::Value>)> {
+    let entry = LedgerHeaderHistoryEntry {
+        hash: Hash::from_str(&ledger.hash)?,
+        header: xdr::from_base64::<LedgerHeader>(&ledger.header_xdr)?,
+        ext: LedgerHeaderHistoryEntryExt::V0,
+    };
+    match format {
+        XdrFormat::Base64 => Ok((Some(xdr::to_base64(&entry)?), None)),
+        XdrFormat::Json => Ok((None, Some(serde_json::to_value(&entry)?))),
+    }
+}
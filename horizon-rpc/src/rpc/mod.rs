@@ -1,3 +1,11 @@
+pub mod accounts;
 pub mod api;
+pub mod error;
+pub mod events;
+pub mod fee_stats;
+pub mod ledger_entries;
+pub mod ledgers;
+pub mod transactions;
+pub mod xdr;
 
 pub use api::{StellarRpcApiServer, StellarRpcServer};
\ No newline at end of file
@@ -0,0 +1,54 @@
+// Helpers for presenting Horizon transactions in the RPC response shapes.
+
+use anyhow::{bail, Result};
+use chrono::DateTime;
+
+use crate::models::horizon::TransactionResponse;
+use crate::models::rpc::TransactionInfo;
+
+pub const DEFAULT_LIMIT: u32 = 50;
+// Horizon's maximum page size
+pub const MAX_LIMIT: u32 = 200;
+
+/// Converts an RFC 3339 timestamp from Horizon into unix seconds.
+pub fn unix_timestamp(time: &str) -> Result<i64> {
+    Ok(DateTime::parse_from_rfc3339(time)?.timestamp())
+}
+
+/// The 1-based position of a transaction within its ledger, read from the
+/// TOID in its paging token.
+pub fn application_order(transaction: &TransactionResponse) -> Result<u32> {
+    let toid: i64 = transaction.paging_token.parse()?;
+    Ok(((toid >> 12) & 0xF_FFFF) as u32)
+}
+
+pub fn status(transaction: &TransactionResponse) -> &'static str {
+    if transaction.successful {
+        "SUCCESS"
+    } else {
+        "FAILED"
+    }
+}
+
+/// Checks that a transaction hash is 64 hex characters.
+pub fn validate_hash(hash: &str) -> Result<()> {
+    if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("transaction hash must be 64 hex characters");
+    }
+    Ok(())
+}
+
+/// Converts a Horizon transaction into the RPC `getTransactions` shape.
+pub fn transaction_info(transaction: TransactionResponse) -> Result<TransactionInfo> {
+    Ok(TransactionInfo {
+        status: status(&transaction).to_string(),
+        application_order: application_order(&transaction)?,
+        fee_bump: transaction.fee_bump_transaction.is_some(),
+        ledger: transaction.ledger,
+        created_at: unix_timestamp(&transaction.created_at)?,
+        envelope_xdr: transaction.envelope_xdr,
+        result_xdr: transaction.result_xdr,
+        result_meta_xdr: transaction.result_meta_xdr,
+        tx_hash: transaction.hash,
+    })
+}
@@ -0,0 +1,22 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use stellar_xdr::{Limits, ReadXdr, WriteXdr};
+
+// Maximum nesting depth accepted when decoding client supplied XDR
+const DEPTH_LIMIT: u32 = 500;
+
+/// Decodes a base64 encoded XDR value.
+pub fn from_base64<T: ReadXdr>(encoded: &str) -> Result<T> {
+    let bytes = STANDARD.decode(encoded)?;
+    let limits = Limits {
+        depth: DEPTH_LIMIT,
+        len: bytes.len(),
+    };
+    Ok(T::from_xdr(bytes, limits)?)
+}
+
+/// Encodes an XDR value as base64.
+pub fn to_base64<T: WriteXdr>(value: &T) -> Result<String> {
+    let bytes = value.to_xdr(Limits::none())?;
+    Ok(STANDARD.encode(bytes))
+}
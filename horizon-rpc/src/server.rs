@@ -0,0 +1,203 @@
+// The JSON-RPC server, serving HTTP and WebSocket clients on one address,
+// along with plain HTTP endpoints for load balancers and scrapers.
+//
+// Connections are accepted here rather than by jsonrpsee so that each request
+// can be tagged with the client that made it, which jsonrpsee passes on to the
+// middleware handling each call.
+
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use jsonrpsee::core::EmptyServerParams;
+use jsonrpsee::server::{
+    serve_with_graceful_shutdown, stop_channel, BatchRequestConfig, HttpBody, HttpRequest, HttpResponse,
+    Methods, MethodsError, RpcServiceBuilder, Server, ServerHandle,
+};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tower::{Service, ServiceBuilder};
+use tower_http::cors::{Any, CorsLayer};
+
+use crate::metrics::{self, RpcMetrics};
+use crate::rate_limit::{RateLimit, RateLimiter, API_KEY_HEADER};
+use crate::telemetry::RpcTracing;
+
+pub const DEFAULT_MAX_REQUEST_BODY_SIZE: u32 = 1024 * 1024;
+// How long to wait before accepting again after a failure, e.g. when out of
+// file descriptors, rather than spinning until it clears
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// How the server treats requests.
+#[derive(Debug)]
+pub struct ServerConfig {
+    pub batch_config: BatchRequestConfig,
+    pub max_request_body_size: u32,
+    // The origins browsers may call from, `*` for any. Without any, no CORS
+    // headers are sent
+    pub cors_origins: Vec<String>,
+    pub rate_limiter: RateLimiter,
+    // Proxies trusted to name the client in X-Forwarded-For
+    pub trusted_proxies: Vec<IpAddr>,
+    // Serve `/metrics` alongside the JSON-RPC server
+    pub serve_metrics: bool,
+}
+
+/// Binds `address` and serves `methods` on it until the returned handle is
+/// stopped. Returns the bound address, which differs from `address` if it
+/// has port 0.
+pub async fn start(
+    address: SocketAddr,
+    methods: impl Into<Methods>,
+    config: ServerConfig,
+) -> anyhow::Result<(SocketAddr, ServerHandle)> {
+    let methods: Methods = methods.into();
+    let method_names: Arc<HashSet<String>> = Arc::new(methods.method_names().map(String::from).collect());
+    let rate_limiter = Arc::new(config.rate_limiter);
+    let cors = cors_layer(&config.cors_origins)?;
+    let serve_metrics = config.serve_metrics;
+    let trusted_proxies: Arc<HashSet<IpAddr>> = Arc::new(config.trusted_proxies.into_iter().collect());
+
+    let limiter = rate_limiter.clone();
+    let rpc_middleware = RpcServiceBuilder::new()
+        .layer_fn(RpcTracing::new)
+        .layer_fn(move |service| RpcMetrics::new(service, method_names.clone()))
+        .layer_fn(move |service| RateLimit::new(service, limiter.clone()));
+    let service_builder = Server::builder()
+        .set_batch_request_config(config.batch_config)
+        .max_request_body_size(config.max_request_body_size)
+        .set_rpc_middleware(rpc_middleware)
+        .to_service_builder();
+
+    let listener = TcpListener::bind(address).await?;
+    let local_addr = listener.local_addr()?;
+    let (stop_handle, server_handle) = stop_channel();
+
+    tokio::spawn(async move {
+        loop {
+            let (socket, remote_addr) = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        tracing::warn!("Failed to accept connection: {}", e);
+                        tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                        continue;
+                    }
+                },
+                _ = stop_handle.clone().shutdown() => break,
+            };
+
+            let rpc_service = service_builder.clone().build(methods.clone(), stop_handle.clone());
+            let methods = methods.clone();
+            let rate_limiter = rate_limiter.clone();
+            let trusted_proxies = trusted_proxies.clone();
+            let service = tower::service_fn(move |mut request: HttpRequest<_>| {
+                let api_key = request
+                    .headers()
+                    .get(API_KEY_HEADER)
+                    .and_then(|value| value.to_str().ok());
+                let ip = client_ip(remote_addr.ip(), &request, &trusted_proxies);
+                let client = rate_limiter.client(ip, api_key);
+                request.extensions_mut().insert(client);
+
+                let is_get = request.method() == "GET";
+                let path = request.uri().path().to_string();
+                let methods = methods.clone();
+                let mut rpc_service = rpc_service.clone();
+                async move {
+                    match path.as_str() {
+                        "/health" if is_get => Ok(health(&methods).await),
+                        "/metrics" if is_get && serve_metrics => Ok(metrics()),
+                        _ => rpc_service.call(request).await.map_err(|e| anyhow::anyhow!("{:?}", e)),
+                    }
+                }
+            });
+            let service = ServiceBuilder::new().option_layer(cors.clone()).service(service);
+
+            let stopped = stop_handle.clone().shutdown();
+            tokio::spawn(async move {
+                if let Err(e) = serve_with_graceful_shutdown(socket, service, stopped).await {
+                    tracing::debug!("Connection from {} failed: {}", remote_addr, e);
+                }
+            });
+        }
+    });
+
+    Ok((local_addr, server_handle))
+}
+
+// The address of the client that made a request. Requests from a trusted
+// proxy are attributed to the last address in X-Forwarded-For that is not
+// itself a trusted proxy, since earlier entries can be set by the client
+fn client_ip<B>(remote_ip: IpAddr, request: &HttpRequest<B>, trusted_proxies: &HashSet<IpAddr>) -> IpAddr {
+    if !trusted_proxies.contains(&remote_ip) {
+        return remote_ip;
+    }
+
+    let forwarded: Vec<&str> = request
+        .headers()
+        .get_all(FORWARDED_FOR_HEADER)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+    let mut client = remote_ip;
+    for address in forwarded.iter().rev() {
+        match address.trim().parse::<IpAddr>() {
+            Ok(ip) => client = ip,
+            Err(_) => break,
+        }
+        if !trusted_proxies.contains(&client) {
+            break;
+        }
+    }
+    client
+}
+
+fn cors_layer(origins: &[String]) -> anyhow::Result<Option<CorsLayer>> {
+    if origins.is_empty() {
+        return Ok(None);
+    }
+    let layer = CorsLayer::new().allow_methods(Any).allow_headers(Any);
+    if origins.iter().any(|origin| origin == "*") {
+        return Ok(Some(layer.allow_origin(Any)));
+    }
+    let origins = origins
+        .iter()
+        .map(|origin| origin.parse())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow::anyhow!("invalid CORS origin: {}", e))?;
+    Ok(Some(layer.allow_origin(origins)))
+}
+
+// Answers `GET /health` with the result of getHealth, or 503 with its error
+async fn health(methods: &Methods) -> HttpResponse {
+    match methods.call::<_, Value>("getHealth", EmptyServerParams::new()).await {
+        Ok(health) => response(200, "application/json", health.to_string()),
+        Err(MethodsError::JsonRpc(e)) => {
+            let body = json!({ "status": "unhealthy", "code": e.code(), "message": e.message() });
+            response(503, "application/json", body.to_string())
+        }
+        Err(e) => {
+            let body = json!({ "status": "unhealthy", "message": e.to_string() });
+            response(503, "application/json", body.to_string())
+        }
+    }
+}
+
+fn metrics() -> HttpResponse {
+    match metrics::render() {
+        Ok(body) => response(200, prometheus::TEXT_FORMAT, body),
+        Err(e) => response(500, "text/plain", e.to_string()),
+    }
+}
+
+fn response(status: u16, content_type: &'static str, body: String) -> HttpResponse {
+    HttpResponse::builder()
+        .status(status)
+        .header("content-type", content_type)
+        .body(HttpBody::from(body))
+        .expect("status and content type are valid")
+}
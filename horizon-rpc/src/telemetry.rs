@@ -0,0 +1,157 @@
+// Logging and request tracing.
+//
+// Each JSON-RPC call runs in an `rpc_call` span with the method, request id
+// and a summary of the params, and each request to Horizon in a
+// `horizon_request` span inside it, so every log line names the call it
+// belongs to. With an OTLP endpoint configured, the spans are also exported
+// to a collector, which shows where the time of a slow call went.
+
+use std::time::Instant;
+
+use anyhow::Context;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::server::MethodResponse;
+use jsonrpsee::types::Request;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use tracing::field::Empty;
+use tracing::Instrument;
+use tracing_subscriber::filter::{EnvFilter, LevelFilter, Targets};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, Layer};
+
+use crate::config::LogFormat;
+
+// Params are cut to this many characters in spans, since a transaction
+// envelope can be many kilobytes
+const MAX_PARAMS_LEN: usize = 200;
+
+/// Keeps the trace exporter running until shut down.
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Telemetry {
+    /// Exports any spans not yet sent to the collector.
+    pub fn shutdown(self) {
+        if let Some(provider) = self.provider {
+            if let Err(e) = provider.shutdown() {
+                tracing::warn!("Failed to export remaining traces: {}", e);
+            }
+        }
+    }
+}
+
+/// Installs the global subscriber, writing logs in `format` at the level set
+/// by `RUST_LOG`, and exporting the server's spans to `otlp_endpoint` if
+/// given. Lines logged through the `log` crate by dependencies are included.
+pub fn init(format: LogFormat, otlp_endpoint: Option<&str>) -> anyhow::Result<Telemetry> {
+    let logs = match format {
+        LogFormat::Text => fmt::layer().boxed(),
+        LogFormat::Json => fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    };
+
+    let provider = otlp_endpoint
+        .map(|endpoint| {
+            let exporter = SpanExporter::builder()
+                .with_http()
+                .with_endpoint(endpoint)
+                .build()
+                .with_context(|| format!("invalid OTLP endpoint {}", endpoint))?;
+            let resource = Resource::builder().with_service_name(env!("CARGO_PKG_NAME")).build();
+            anyhow::Ok(
+                SdkTracerProvider::builder()
+                    .with_batch_exporter(exporter)
+                    .with_resource(resource)
+                    .build(),
+            )
+        })
+        .transpose()?;
+
+    // Traces are exported whatever the log level, but only the server's own
+    // spans, not those of its dependencies
+    let traces = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+            .with_filter(Targets::new().with_target(env!("CARGO_CRATE_NAME"), LevelFilter::INFO))
+    });
+
+    tracing_subscriber::registry()
+        .with(logs.with_filter(EnvFilter::from_default_env()))
+        .with(traces)
+        .try_init()?;
+
+    Ok(Telemetry { provider })
+}
+
+/// JSON-RPC middleware that runs each call in a span and logs its outcome
+/// and duration.
+#[derive(Debug, Clone)]
+pub struct RpcTracing<S> {
+    service: S,
+}
+
+impl<S> RpcTracing<S> {
+    pub fn new(service: S) -> Self {
+        Self { service }
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for RpcTracing<S>
+where
+    S: RpcServiceT<'a> + Send + Sync,
+    S::Future: 'a,
+{
+    type Future = BoxFuture<'a, MethodResponse>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let span = tracing::info_span!(
+            "rpc_call",
+            otel.name = %request.method_name(),
+            otel.kind = "server",
+            otel.status_code = Empty,
+            method = %request.method_name(),
+            request_id = %request.id(),
+            params = %summarize(request.params().as_str().unwrap_or_default()),
+            error_code = Empty,
+        );
+        let started_at = Instant::now();
+        let response = span.in_scope(|| self.service.call(request));
+
+        async move {
+            let response = response.await;
+            let duration_ms = started_at.elapsed().as_millis() as u64;
+            match response.as_error_code() {
+                Some(code) => {
+                    let span = tracing::Span::current();
+                    span.record("error_code", code);
+                    span.record("otel.status_code", "error");
+                    tracing::info!(duration_ms, "RPC call failed");
+                }
+                None => tracing::info!(duration_ms, "RPC call succeeded"),
+            }
+            response
+        }
+        .instrument(span)
+        .boxed()
+    }
+}
+
+// Cuts params down to fit on a log line
+fn summarize(params: &str) -> String {
+    let params = params.trim();
+    let summary = match params.char_indices().nth(MAX_PARAMS_LEN) {
+        Some((end, _)) => format!("{}...", &params[..end]),
+        None => params.to_string(),
+    };
+    summary.replace(['\n', '\r', '\t'], " ")
+}
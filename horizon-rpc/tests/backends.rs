@@ -0,0 +1,31 @@
+// Taking Horizon instances that fall behind out of rotation.
+
+use horizon_rpc::horizon::backend::Backends;
+use horizon_rpc::horizon::Strategy;
+use url::Url;
+
+fn backends(max_ledger_lag: u32) -> Backends {
+    let urls = vec![Url::parse("http://a.example/").unwrap(), Url::parse("http://b.example/").unwrap()];
+    Backends::new(urls, Strategy::Priority, max_ledger_lag, None)
+}
+
+fn healthy(backends: &Backends) -> Vec<bool> {
+    backends.all().iter().map(|backend| backend.is_healthy()).collect()
+}
+
+#[test]
+fn lagging_backends_are_skipped() {
+    let backends = backends(5);
+    assert_eq!(backends.record_health(&[Some(100), Some(95)]), Some(100));
+    assert_eq!(healthy(&backends), vec![true, true]);
+
+    assert_eq!(backends.record_health(&[Some(100), Some(94)]), Some(100));
+    assert_eq!(healthy(&backends), vec![true, false]);
+}
+
+#[test]
+fn large_max_lag_keeps_every_backend() {
+    let backends = backends(u32::MAX);
+    assert_eq!(backends.record_health(&[Some(100), Some(1)]), Some(100));
+    assert_eq!(healthy(&backends), vec![true, true]);
+}
@@ -0,0 +1,49 @@
+// The limits on the response cache.
+
+use horizon_rpc::horizon::cache::ResponseCache;
+
+#[test]
+fn evicts_oldest_entries_over_entry_limit() {
+    let cache = ResponseCache::new(2, 1024);
+    cache.insert("ledgers/1", "1".to_string());
+    cache.insert("ledgers/2", "2".to_string());
+    cache.insert("ledgers/3", "3".to_string());
+    assert_eq!(cache.get("ledgers/1"), None);
+    assert_eq!(cache.get("ledgers/2").as_deref(), Some("2"));
+    assert_eq!(cache.get("ledgers/3").as_deref(), Some("3"));
+}
+
+#[test]
+fn evicts_oldest_entries_over_byte_limit() {
+    // Each entry takes 9 bytes of path and 91 of body
+    let cache = ResponseCache::new(100, 1000);
+    for sequence in 0..10 {
+        cache.insert(&format!("ledgers/{}", sequence), "x".repeat(91));
+    }
+    assert!(cache.get("ledgers/0").is_some());
+
+    cache.insert("ledgers/a", "x".repeat(91));
+    assert!(cache.get("ledgers/0").is_none());
+    assert!(cache.get("ledgers/1").is_some());
+    assert!(cache.get("ledgers/a").is_some());
+}
+
+#[test]
+fn replacing_an_entry_frees_its_bytes() {
+    let cache = ResponseCache::new(100, 1000);
+    for _ in 0..20 {
+        cache.insert("ledgers/1", "x".repeat(91));
+    }
+    cache.insert("ledgers/2", "x".repeat(91));
+    assert!(cache.get("ledgers/1").is_some());
+    assert!(cache.get("ledgers/2").is_some());
+}
+
+#[test]
+fn skips_large_bodies() {
+    let cache = ResponseCache::new(100, 1000);
+    cache.insert("ledgers/1", "x".repeat(101));
+    assert!(cache.get("ledgers/1").is_none());
+    cache.insert("ledgers/1", "x".repeat(91));
+    assert!(cache.get("ledgers/1").is_some());
+}
@@ -0,0 +1,324 @@
+// Helpers shared by the integration tests: a mock Horizon serving the
+// recorded responses in tests/fixtures/horizon, and a horizon-rpc server
+// running against it.
+//
+// Fixtures are named after the Horizon path they answer. A collection such as
+// `/ledgers` is a page of records that the mock orders, pages and streams
+// like Horizon. A single resource such as `/ledgers/5` is served from
+// `ledgers/5.json` if it exists, otherwise from the collection's record with
+// that id, hash or sequence.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use horizon_rpc::horizon::{ClientConfig, HorizonClient};
+use horizon_rpc::rate_limit::RateLimiter;
+use horizon_rpc::rpc::{StellarRpcApiServer, StellarRpcServer};
+use horizon_rpc::server::{self, ServerConfig, DEFAULT_MAX_REQUEST_BODY_SIZE};
+use jsonrpsee::server::{BatchRequestConfig, ServerHandle};
+use serde_json::{json, Value};
+
+/// The account the fixtures hold.
+pub const ACCOUNT: &str = "GAAZI4TCR3TY5OJHCTJC2A4QSY6CJWJH5IAJTGKIN2ER7LBNVKOCCWN7";
+pub const OLDEST_LEDGER: u32 = 2;
+pub const LATEST_LEDGER: u32 = 10;
+
+// The fixtures closed long ago, so getHealth would otherwise fail
+const MAX_LEDGER_AGE: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+// How many of a collection's latest records a stream from `now` sends, standing
+// in for records that close after the stream opens
+const STREAM_NOW_RECORDS: usize = 2;
+
+// Horizon's default page size
+const DEFAULT_LIMIT: usize = 10;
+
+#[derive(Default)]
+struct State {
+    // Paths that fail with a status for a number of requests
+    failures: Mutex<HashMap<String, (u16, usize)>>,
+    requests: Mutex<Vec<String>>,
+}
+
+/// A stand-in for Horizon serving the recorded fixtures.
+pub struct MockHorizon {
+    pub url: String,
+    state: Arc<State>,
+}
+
+impl MockHorizon {
+    pub async fn start() -> Self {
+        let state = Arc::new(State::default());
+        let data = web::Data::from(state.clone());
+        let server = HttpServer::new(move || App::new().app_data(data.clone()).default_service(web::to(handle)))
+            .workers(1)
+            .disable_signals()
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let url = format!("http://{}/", server.addrs()[0]);
+        tokio::spawn(server.run());
+        Self { url, state }
+    }
+
+    /// Makes the next `times` requests to `path` fail with `status`.
+    pub fn fail(&self, path: &str, status: u16, times: usize) {
+        self.state
+            .failures
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), (status, times));
+    }
+
+    /// The requests received so far, as `METHOD /path?query`.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.requests.lock().unwrap().clone()
+    }
+}
+
+async fn handle(request: HttpRequest, state: web::Data<State>) -> HttpResponse {
+    let path = request.path().to_string();
+    let query: HashMap<String, String> = url::form_urlencoded::parse(request.query_string().as_bytes())
+        .into_owned()
+        .collect();
+    state
+        .requests
+        .lock()
+        .unwrap()
+        .push(format!("{} {}?{}", request.method(), path, request.query_string()));
+
+    if let Some((status, times)) = state.failures.lock().unwrap().get_mut(&path) {
+        if *times > 0 {
+            *times -= 1;
+            return problem(*status);
+        }
+    }
+
+    if request.method() == "POST" {
+        return match fixture(&path) {
+            Some(body) => HttpResponse::Created().json(body),
+            None => problem(404),
+        };
+    }
+
+    let segments: Vec<&str> = path.trim_matches('/').split('/').filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        [] => fixture("/root").map_or_else(|| problem(404), |body| HttpResponse::Ok().json(body)),
+        [collection] => {
+            let Some(page) = fixture(collection) else {
+                return problem(404);
+            };
+            let Some(records) = page["_embedded"]["records"].as_array().cloned() else {
+                return HttpResponse::Ok().json(page);
+            };
+            let streaming = request
+                .headers()
+                .get("accept")
+                .and_then(|accept| accept.to_str().ok())
+                .is_some_and(|accept| accept.contains("text/event-stream"));
+            if streaming {
+                stream(records, &query)
+            } else {
+                HttpResponse::Ok().json(json!({ "_links": {}, "_embedded": { "records": select(records, &query) } }))
+            }
+        }
+        [collection, id] => {
+            let resource = fixture(&format!("{}/{}", collection, id)).or_else(|| {
+                fixture(collection)?["_embedded"]["records"]
+                    .as_array()?
+                    .iter()
+                    .find(|record| ["id", "hash", "sequence"].iter().any(|key| matches_id(&record[key], id)))
+                    .cloned()
+            });
+            resource.map_or_else(|| problem(404), |body| HttpResponse::Ok().json(body))
+        }
+        _ => problem(404),
+    }
+}
+
+fn fixture(name: &str) -> Option<Value> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/horizon")
+        .join(format!("{}.json", name.trim_start_matches('/')));
+    let contents = std::fs::read_to_string(path).ok()?;
+    Some(serde_json::from_str(&contents).expect("fixtures are valid JSON"))
+}
+
+fn matches_id(value: &Value, id: &str) -> bool {
+    match value {
+        Value::String(value) => value == id,
+        Value::Number(value) => value.to_string() == id,
+        _ => false,
+    }
+}
+
+// Paging tokens are TOIDs, or for effects a TOID and an index joined by `-`
+fn token(record: &Value) -> Vec<i64> {
+    record["paging_token"]
+        .as_str()
+        .unwrap_or_default()
+        .split('-')
+        .map(|part| part.parse().unwrap_or_default())
+        .collect()
+}
+
+// Orders and pages records like Horizon
+fn select(mut records: Vec<Value>, query: &HashMap<String, String>) -> Vec<Value> {
+    let descending = query.get("order").is_some_and(|order| order == "desc");
+    records.sort_by_key(token);
+    if descending {
+        records.reverse();
+    }
+    if let Some(cursor) = query.get("cursor").filter(|cursor| *cursor != "now") {
+        let cursor = token(&json!({ "paging_token": cursor }));
+        records.retain(|record| if descending { token(record) < cursor } else { token(record) > cursor });
+    }
+    let limit = query
+        .get("limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(DEFAULT_LIMIT);
+    records.into_iter().take(limit).collect()
+}
+
+// Sends the records after the cursor as server-sent events, then ends the
+// stream as if the connection dropped
+fn stream(mut records: Vec<Value>, query: &HashMap<String, String>) -> HttpResponse {
+    records.sort_by_key(token);
+    let records = match query.get("cursor").map(String::as_str) {
+        None | Some("now") => records.split_off(records.len().saturating_sub(STREAM_NOW_RECORDS)),
+        Some(_) => select(records, &{
+            let mut query = query.clone();
+            query.insert("limit".to_string(), usize::MAX.to_string());
+            query
+        }),
+    };
+
+    let mut body = String::from("retry: 1000\nevent: open\ndata: \"hello\"\n\n");
+    for record in records {
+        body.push_str(&format!("id: {}\ndata: {}\n\n", record["paging_token"].as_str().unwrap_or_default(), record));
+    }
+    HttpResponse::Ok().content_type("text/event-stream").body(body)
+}
+
+fn problem(status: u16) -> HttpResponse {
+    let status = actix_web::http::StatusCode::from_u16(status).unwrap();
+    let type_ = match status.as_u16() {
+        404 => "not_found",
+        429 => "rate_limit_exceeded",
+        400 => "bad_request",
+        _ => "server_error",
+    };
+    HttpResponse::build(status).json(json!({
+        "type": format!("https://stellar.org/horizon-errors/{}", type_),
+        "title": status.canonical_reason().unwrap_or_default(),
+        "status": status.as_u16(),
+    }))
+}
+
+/// A client for the mock, retrying quickly so failure tests stay fast.
+pub fn horizon_client(horizon: &MockHorizon) -> HorizonClient {
+    let config = ClientConfig {
+        timeout: Duration::from_secs(5),
+        max_retries: 1,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+        ..ClientConfig::default()
+    };
+    HorizonClient::new(std::slice::from_ref(&horizon.url), config).unwrap()
+}
+
+/// The RPC server against the mock, as the binary sets it up.
+pub fn rpc_server(horizon: &MockHorizon) -> StellarRpcServer {
+    StellarRpcServer::new(horizon_client(horizon)).with_max_ledger_age(MAX_LEDGER_AGE)
+}
+
+pub fn server_config() -> ServerConfig {
+    ServerConfig {
+        batch_config: BatchRequestConfig::Limit(100),
+        max_request_body_size: DEFAULT_MAX_REQUEST_BODY_SIZE,
+        cors_origins: Vec::new(),
+        rate_limiter: RateLimiter::default(),
+        trusted_proxies: Vec::new(),
+        serve_metrics: false,
+    }
+}
+
+/// A horizon-rpc server listening on a local port, backed by a mock Horizon.
+pub struct TestServer {
+    pub url: String,
+    pub horizon: MockHorizon,
+    pub client: reqwest::Client,
+    handle: ServerHandle,
+}
+
+impl TestServer {
+    pub async fn start() -> Self {
+        Self::start_with(|rpc| rpc, server_config()).await
+    }
+
+    /// Starts a server after `configure` adjusts the RPC server.
+    pub async fn start_with(configure: impl FnOnce(StellarRpcServer) -> StellarRpcServer, config: ServerConfig) -> Self {
+        let horizon = MockHorizon::start().await;
+        let rpc = configure(rpc_server(&horizon));
+        let address: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let (address, handle) = server::start(address, rpc.into_rpc(), config).await.unwrap();
+        Self {
+            url: format!("http://{}", address),
+            horizon,
+            client: reqwest::Client::new(),
+            handle,
+        }
+    }
+
+    /// Sends a raw JSON-RPC request body and returns the response body.
+    pub async fn send(&self, body: Value) -> Value {
+        self.client
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap()
+    }
+
+    /// Calls `method`, returning its result or its error object.
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value, Value> {
+        let response = self
+            .send(json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .await;
+        match response.get("error") {
+            Some(error) => Err(error.clone()),
+            None => Ok(response["result"].clone()),
+        }
+    }
+
+    /// Calls `method`, failing the test if it returns an error.
+    pub async fn result(&self, method: &str, params: Value) -> Value {
+        self.call(method, params)
+            .await
+            .unwrap_or_else(|e| panic!("{} failed: {}", method, e))
+    }
+
+    /// Calls `method`, failing the test unless it returns an error, and
+    /// returns the error's code.
+    pub async fn error_code(&self, method: &str, params: Value) -> i64 {
+        let error = self
+            .call(method, params)
+            .await
+            .expect_err(&format!("{} succeeded", method));
+        error["code"].as_i64().unwrap()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.handle.stop();
+    }
+}
@@ -0,0 +1,234 @@
+// The errors each method returns for bad parameters, missing resources and
+// failures upstream.
+
+mod common;
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use common::{server_config, TestServer, ACCOUNT};
+use horizon_rpc::horizon::{ClientConfig, HorizonClient};
+use horizon_rpc::rpc::error::{INTERNAL_ERROR, INVALID_PARAMS, NOT_FOUND, NOT_IMPLEMENTED, UPSTREAM_UNAVAILABLE};
+use horizon_rpc::rpc::{xdr, StellarRpcApiServer, StellarRpcServer};
+use jsonrpsee::core::EmptyServerParams;
+use jsonrpsee::server::MethodsError;
+use serde_json::{json, Value};
+use stellar_xdr::{
+    AccountId, ContractDataDurability, ContractId, Hash, LedgerKey, LedgerKeyAccount, LedgerKeyContractData, ScAddress, ScVal,
+};
+
+// JSON-RPC's code for an unknown method
+const METHOD_NOT_FOUND: i64 = -32601;
+
+// The envelope of the payment in the transaction fixtures
+fn payment_envelope() -> Value {
+    let transactions: Value = serde_json::from_str(include_str!("fixtures/horizon/transactions.json")).unwrap();
+    transactions["_embedded"]["records"][0]["envelope_xdr"].clone()
+}
+
+#[tokio::test]
+async fn unknown_method() {
+    let server = TestServer::start().await;
+    assert_eq!(server.error_code("getLedgerHeader", json!([])).await, METHOD_NOT_FOUND);
+    // Horizon lacks the ledger state to simulate transactions, so the method
+    // is not served
+    assert_eq!(
+        server.error_code("simulateTransaction", json!({ "transaction": payment_envelope() })).await,
+        METHOD_NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn missing_and_malformed_params() {
+    let server = TestServer::start().await;
+    assert_eq!(server.error_code("getTransaction", json!({})).await, INVALID_PARAMS as i64);
+    assert_eq!(server.error_code("getLedger", json!({ "sequence": "five" })).await, INVALID_PARAMS as i64);
+    assert_eq!(
+        server.error_code("getLedgers", json!({ "start_ledger": 2, "xdr_format": "yaml" })).await,
+        INVALID_PARAMS as i64
+    );
+}
+
+#[tokio::test]
+async fn get_health_unhealthy_when_behind() {
+    let server = TestServer::start_with(|rpc| rpc.with_max_ledger_age(Duration::from_secs(30)), server_config()).await;
+    let error = server.call("getHealth", json!([])).await.unwrap_err();
+    assert_eq!(error["code"], UPSTREAM_UNAVAILABLE);
+    assert!(error["message"].as_str().unwrap().contains("since last known ledger closed is too high"));
+}
+
+#[tokio::test]
+async fn upstream_server_errors() {
+    let server = TestServer::start().await;
+    // Fails the first attempt and the retry
+    server.horizon.fail("/", 503, 2);
+    let error = server.call("getNetwork", json!([])).await.unwrap_err();
+    assert_eq!(error["code"], UPSTREAM_UNAVAILABLE);
+    assert_eq!(error["data"]["status"], 503);
+    assert_eq!(error["data"]["type"], "https://stellar.org/horizon-errors/server_error");
+}
+
+#[tokio::test]
+async fn upstream_errors_are_retried() {
+    let server = TestServer::start().await;
+    server.horizon.fail("/", 503, 1);
+    let network = server.result("getNetwork", json!([])).await;
+    assert_eq!(network["protocolVersion"], 21);
+
+    let attempts = server.horizon.requests().iter().filter(|r| r.starts_with("GET /?")).count();
+    assert_eq!(attempts, 2);
+}
+
+#[tokio::test]
+async fn upstream_rate_limits() {
+    let server = TestServer::start().await;
+    server.horizon.fail("/fee_stats", 429, 2);
+    let error = server.call("getFeeStats", json!([])).await.unwrap_err();
+    assert_eq!(error["code"], UPSTREAM_UNAVAILABLE);
+    assert_eq!(error["data"]["status"], 429);
+}
+
+#[tokio::test]
+async fn upstream_unreachable() {
+    // Nothing listens on a port once its listener is dropped
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let config = ClientConfig {
+        timeout: Duration::from_secs(1),
+        max_retries: 0,
+        ..ClientConfig::default()
+    };
+    let client = HorizonClient::new(&[format!("http://127.0.0.1:{}/", port)], config).unwrap();
+    let rpc = StellarRpcServer::new(client).into_rpc();
+
+    match rpc.call::<_, Value>("getNetwork", EmptyServerParams::new()).await {
+        Err(MethodsError::JsonRpc(error)) => assert_eq!(error.code(), UPSTREAM_UNAVAILABLE),
+        result => panic!("getNetwork returned {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn get_ledger_errors() {
+    let server = TestServer::start().await;
+    assert_eq!(server.error_code("getLedger", json!({})).await, INVALID_PARAMS as i64);
+    assert_eq!(
+        server.error_code("getLedger", json!({ "sequence": 5, "hash": "05".repeat(32) })).await,
+        INVALID_PARAMS as i64
+    );
+    assert_eq!(server.error_code("getLedger", json!({ "hash": "not hex" })).await, INVALID_PARAMS as i64);
+    // A hash outside the latest ledgers may be an older ledger's
+    assert_eq!(server.error_code("getLedger", json!({ "hash": "ff".repeat(32) })).await, NOT_IMPLEMENTED as i64);
+
+    let error = server.call("getLedger", json!({ "sequence": 1000 })).await.unwrap_err();
+    assert_eq!(error["code"], NOT_FOUND);
+    assert_eq!(error["data"]["status"], 404);
+}
+
+#[tokio::test]
+async fn get_ledger_entries_errors() {
+    let server = TestServer::start().await;
+    assert_eq!(
+        server.error_code("getLedgerEntries", json!({ "keys": ["not xdr"] })).await,
+        INVALID_PARAMS as i64
+    );
+
+    // Contract data is not served by Horizon
+    let key = xdr::to_base64(&LedgerKey::ContractData(LedgerKeyContractData {
+        contract: ScAddress::Contract(ContractId(Hash([0xcc; 32]))),
+        key: ScVal::LedgerKeyContractInstance,
+        durability: ContractDataDurability::Persistent,
+    }))
+    .unwrap();
+    assert_eq!(
+        server.error_code("getLedgerEntries", json!({ "keys": [key] })).await,
+        NOT_IMPLEMENTED as i64
+    );
+}
+
+#[tokio::test]
+async fn get_ledger_entries_key_cap() {
+    let server = TestServer::start_with(|rpc| rpc.with_max_ledger_entry_keys(2), server_config()).await;
+    let account_id = AccountId::from_str(ACCOUNT).unwrap();
+    let key = xdr::to_base64(&LedgerKey::Account(LedgerKeyAccount { account_id })).unwrap();
+
+    let result = server.result("getLedgerEntries", json!({ "keys": [key, key] })).await;
+    assert_eq!(result["entries"].as_array().unwrap().len(), 2);
+
+    let error = server.call("getLedgerEntries", json!({ "keys": [key, key, key] })).await.unwrap_err();
+    assert_eq!(error["code"], INVALID_PARAMS);
+    assert_eq!(error["message"], "At most 2 keys may be requested, got 3");
+}
+
+#[tokio::test]
+async fn get_account_errors() {
+    let server = TestServer::start().await;
+    for method in ["getAccount", "getBalances"] {
+        assert_eq!(server.error_code(method, json!({ "account": "GABC" })).await, INVALID_PARAMS as i64);
+        assert_eq!(
+            server
+                .error_code(method, json!({ "account": "GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H" }))
+                .await,
+            NOT_FOUND as i64
+        );
+    }
+}
+
+#[tokio::test]
+async fn get_events_errors() {
+    let server = TestServer::start().await;
+    let filters = json!([{ "contractIds": ["GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H"] }]);
+    assert_eq!(
+        server.error_code("getEvents", json!({ "start_ledger": 2, "filters": filters })).await,
+        INVALID_PARAMS as i64
+    );
+    let filters = json!([{ "type": "unknown" }]);
+    assert_eq!(
+        server.error_code("getEvents", json!({ "start_ledger": 2, "filters": filters })).await,
+        INVALID_PARAMS as i64
+    );
+    assert_eq!(
+        server.error_code("getEvents", json!({ "start_ledger": 5, "end_ledger": 5 })).await,
+        INVALID_PARAMS as i64
+    );
+}
+
+#[tokio::test]
+async fn send_transaction_errors() {
+    let server = TestServer::start().await;
+    assert_eq!(
+        server.error_code("sendTransaction", json!({ "transaction": "not xdr" })).await,
+        INVALID_PARAMS as i64
+    );
+
+    server.horizon.fail("/transactions_async", 503, 2);
+    assert_eq!(
+        server.error_code("sendTransaction", json!({ "transaction": payment_envelope() })).await,
+        UPSTREAM_UNAVAILABLE as i64
+    );
+}
+
+#[tokio::test]
+async fn send_transaction_reads_latest_ledger_first() {
+    let server = TestServer::start().await;
+    server.horizon.fail("/", 503, 2);
+    assert_eq!(
+        server.error_code("sendTransaction", json!({ "transaction": payment_envelope() })).await,
+        UPSTREAM_UNAVAILABLE as i64
+    );
+
+    // The transaction is not submitted, so the client can safely retry
+    assert!(!server.horizon.requests().iter().any(|r| r.starts_with("POST /transactions_async")));
+}
+
+#[tokio::test]
+async fn get_transaction_errors() {
+    let server = TestServer::start().await;
+    assert_eq!(server.error_code("getTransaction", json!({ "hash": "abc" })).await, INVALID_PARAMS as i64);
+}
+
+#[tokio::test]
+async fn invalid_upstream_responses() {
+    let server = TestServer::start().await;
+    // A problem document where fee stats are expected
+    server.horizon.fail("/fee_stats", 200, 1);
+    assert_eq!(server.error_code("getFeeStats", json!([])).await, INTERNAL_ERROR as i64);
+}
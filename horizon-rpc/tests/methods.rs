@@ -0,0 +1,400 @@
+// Each JSON-RPC method against the mock Horizon, checking that the recorded
+// Horizon responses come back in the Stellar RPC shapes.
+
+mod common;
+
+use std::str::FromStr;
+
+use common::{TestServer, ACCOUNT, LATEST_LEDGER, OLDEST_LEDGER};
+use horizon_rpc::rpc::xdr;
+use serde_json::{json, Value};
+use stellar_xdr::{
+    AccountId, AlphaNum4, AssetCode4, ContractId, Hash, LedgerHeaderHistoryEntry, LedgerKey, LedgerKeyAccount, LedgerKeyTrustLine,
+    ScAddress, ScSymbol, ScVal, TransactionEnvelope, TransactionMeta, TrustLineAsset,
+};
+
+const PAYMENT_ENVELOPE: &str = "AAAAAgAAAAABlHJijueOuScU0i0DkJY8JNkn6gCZmUhuiR+sLaqcIQAAAGQAAAAFAAAQAAAAAAAAAAAAAAAAAQAAAAAAAAABAAAAAAGUcmKO5465JxTSLQOQljwk2SfqAJmZSG6JH6wtqpwhAAAAAAAAAAAAmJaAAAAAAAAAAAA=";
+
+fn transaction_hash(ledger: u8, index: u8) -> String {
+    format!("{:02x}{:02x}{}", ledger, index, "ab".repeat(30))
+}
+
+fn transactions_cursor(ledger: i64, index: i64) -> String {
+    ((ledger << 32) | (index << 12)).to_string()
+}
+
+fn contract_id(byte: u8) -> String {
+    ScAddress::Contract(ContractId(Hash([byte; 32]))).to_string()
+}
+
+fn symbol(name: &str) -> String {
+    xdr::to_base64(&ScVal::Symbol(ScSymbol(name.try_into().unwrap()))).unwrap()
+}
+
+#[tokio::test]
+async fn get_health() {
+    let server = TestServer::start().await;
+    let health = server.result("getHealth", json!([])).await;
+    assert_eq!(
+        health,
+        json!({
+            "status": "healthy",
+            "latestLedger": LATEST_LEDGER,
+            "oldestLedger": OLDEST_LEDGER,
+            "ledgerRetentionWindow": LATEST_LEDGER - OLDEST_LEDGER + 1,
+        })
+    );
+}
+
+#[tokio::test]
+async fn get_network() {
+    let server = TestServer::start().await;
+    let network = server.result("getNetwork", json!([])).await;
+    assert_eq!(network["passphrase"], "Test SDF Network ; September 2015");
+    assert_eq!(network["protocolVersion"], 21);
+}
+
+#[tokio::test]
+async fn get_version_info() {
+    let server = TestServer::start().await;
+    let info = server.result("getVersionInfo", json!([])).await;
+    assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(info["protocolVersion"], 21);
+    assert!(info["captiveCoreVersion"].as_str().unwrap().starts_with("stellar-core 21.0.0"));
+    assert!(info["horizonVersion"].as_str().unwrap().starts_with("2.30.0"));
+}
+
+#[tokio::test]
+async fn get_latest_ledger() {
+    let server = TestServer::start().await;
+    let ledger = server.result("getLatestLedger", json!([])).await;
+    assert_eq!(ledger["sequence"], LATEST_LEDGER);
+    assert_eq!(ledger["hash"], "0a".repeat(32));
+    assert!(ledger["closedAt"].is_string());
+    assert_eq!(ledger["protocolVersion"], 21);
+}
+
+#[tokio::test]
+async fn get_ledger_by_sequence() {
+    let server = TestServer::start().await;
+    let ledger = server.result("getLedger", json!({ "sequence": 5 })).await;
+    assert_eq!(ledger["sequence"], 5);
+    assert_eq!(ledger["hash"], "05".repeat(32));
+    assert_eq!(ledger["protocolVersion"], 21);
+    assert_eq!(ledger["baseFeeInStroops"], 100);
+    assert_eq!(ledger["baseReserveInStroops"], 5_000_000);
+    assert_eq!(ledger["maxTxSetSize"], 1000);
+    assert!(ledger["headerXdr"].is_string());
+    assert!(ledger.get("headerJson").is_none());
+}
+
+#[tokio::test]
+async fn get_ledger_by_hash_as_json() {
+    let server = TestServer::start().await;
+    let ledger = server
+        .result("getLedger", json!({ "hash": "07".repeat(32), "xdr_format": "json" }))
+        .await;
+    assert_eq!(ledger["sequence"], 7);
+    assert_eq!(ledger["headerJson"]["hash"], "07".repeat(32));
+    assert_eq!(ledger["headerJson"]["header"]["ledger_seq"], 7);
+    assert!(ledger.get("headerXdr").is_none());
+}
+
+#[tokio::test]
+async fn get_ledgers() {
+    let server = TestServer::start().await;
+    let result = server
+        .result("getLedgers", json!({ "start_ledger": 4, "pagination": { "limit": 3 } }))
+        .await;
+    let sequences: Vec<_> = result["ledgers"].as_array().unwrap().iter().map(|l| l["sequence"].clone()).collect();
+    assert_eq!(sequences, vec![json!(4), json!(5), json!(6)]);
+    assert_eq!(result["latestLedger"], LATEST_LEDGER);
+    assert_eq!(result["oldestLedger"], OLDEST_LEDGER);
+    assert_eq!(result["latestLedgerCloseTime"], 1714521650);
+    assert_eq!(result["cursor"], (6i64 << 32).to_string());
+
+    let ledger = &result["ledgers"][0];
+    assert_eq!(ledger["hash"], "04".repeat(32));
+    assert_eq!(ledger["ledgerCloseTime"], "1714521620");
+
+    // The header comes as a LedgerHeaderHistoryEntry, like Stellar RPC's
+    let entry: LedgerHeaderHistoryEntry = xdr::from_base64(ledger["headerXdr"].as_str().unwrap()).unwrap();
+    assert_eq!(entry.hash, Hash([0x04; 32]));
+    assert_eq!(entry.header.ledger_seq, 4);
+    assert_eq!(entry.header.previous_ledger_hash, Hash([0x03; 32]));
+}
+
+#[tokio::test]
+async fn get_ledger_entries() {
+    let server = TestServer::start().await;
+    let account_id = AccountId::from_str(ACCOUNT).unwrap();
+    let account_key = xdr::to_base64(&LedgerKey::Account(LedgerKeyAccount { account_id: account_id.clone() })).unwrap();
+    let trustline_key = xdr::to_base64(&LedgerKey::Trustline(LedgerKeyTrustLine {
+        account_id: account_id.clone(),
+        asset: TrustLineAsset::CreditAlphanum4(AlphaNum4 {
+            asset_code: AssetCode4(*b"USD\0"),
+            issuer: account_id,
+        }),
+    }))
+    .unwrap();
+
+    let result = server
+        .result("getLedgerEntries", json!({ "keys": [account_key, trustline_key] }))
+        .await;
+    assert_eq!(result["latestLedger"], LATEST_LEDGER);
+    let entries = result["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["key"], account_key);
+    assert_eq!(entries[0]["lastModifiedLedgerSeq"], 8);
+    assert_eq!(entries[1]["key"], trustline_key);
+    assert_eq!(entries[1]["lastModifiedLedgerSeq"], 5);
+}
+
+#[tokio::test]
+async fn get_ledger_entries_leaves_out_missing_entries() {
+    let server = TestServer::start().await;
+    let missing = AccountId::from_str("GBRPYHIL2CI3FNQ4BXLFMNDLFJUNPU2HY3ZMFSHONUCEOASW7QC7OX2H").unwrap();
+    let key = xdr::to_base64(&LedgerKey::Account(LedgerKeyAccount { account_id: missing })).unwrap();
+
+    let result = server.result("getLedgerEntries", json!({ "keys": [key] })).await;
+    assert_eq!(result["entries"], json!([]));
+}
+
+#[tokio::test]
+async fn get_account() {
+    let server = TestServer::start().await;
+    let result = server.result("getAccount", json!({ "account": ACCOUNT })).await;
+    assert_eq!(result["latestLedger"], LATEST_LEDGER);
+
+    let account = &result["account"];
+    assert_eq!(account["accountId"], ACCOUNT);
+    assert_eq!(account["sequence"], "25769803776");
+    assert_eq!(account["lastModifiedLedgerSeq"], 8);
+    assert_eq!(account["balances"].as_array().unwrap().len(), 3);
+    assert!(account["entryXdr"].is_string());
+}
+
+#[tokio::test]
+async fn get_balances() {
+    let server = TestServer::start().await;
+    let result = server
+        .result("getBalances", json!({ "account": ACCOUNT, "xdr_format": "json" }))
+        .await;
+    assert_eq!(result["accountId"], ACCOUNT);
+    assert_eq!(result["latestLedger"], LATEST_LEDGER);
+
+    let balances = result["balances"].as_array().unwrap();
+    let types: Vec<_> = balances.iter().map(|b| b["assetType"].clone()).collect();
+    assert_eq!(types, vec![json!("credit_alphanum4"), json!("liquidity_pool_shares"), json!("native")]);
+
+    // Trustlines come with their entries, the native balance with none
+    assert_eq!(balances[0]["assetCode"], "USD");
+    assert_eq!(balances[0]["balance"], "10.5000000");
+    assert_eq!(balances[0]["entryJson"]["balance"], "105000000");
+    assert_eq!(balances[1]["liquidityPoolId"], "ee".repeat(32));
+    assert!(balances[1]["entryJson"].is_object());
+    assert!(balances[2].get("entryJson").is_none());
+}
+
+#[tokio::test]
+async fn get_events() {
+    let server = TestServer::start().await;
+    let result = server.result("getEvents", json!({ "start_ledger": OLDEST_LEDGER })).await;
+    assert_eq!(result["latestLedger"], LATEST_LEDGER);
+
+    let events = result["events"].as_array().unwrap();
+    let ids: Vec<_> = events.iter().map(|e| e["id"].clone()).collect();
+    assert_eq!(
+        ids,
+        vec![
+            json!(format!("{:019}-{:010}", (6i64 << 32) | (1 << 12) | 1, 0)),
+            json!(format!("{:019}-{:010}", (7i64 << 32) | (1 << 12) | 1, 0)),
+            json!(format!("{:019}-{:010}", (7i64 << 32) | (1 << 12) | 1, 1)),
+        ]
+    );
+
+    let event = &events[0];
+    assert_eq!(event["type"], "contract");
+    assert_eq!(event["ledger"], 6);
+    assert_eq!(event["contractId"], contract_id(0xcc));
+    assert_eq!(event["txHash"], transaction_hash(6, 1));
+    assert_eq!(event["inSuccessfulContractCall"], true);
+    assert_eq!(event["topic"][0], symbol("transfer"));
+}
+
+#[tokio::test]
+async fn get_events_with_filters() {
+    let server = TestServer::start().await;
+    let filters = json!([{ "contractIds": [contract_id(0xdd)], "topics": [[symbol("transfer"), "*"]] }]);
+    let result = server
+        .result("getEvents", json!({ "start_ledger": OLDEST_LEDGER, "filters": filters }))
+        .await;
+
+    let events = result["events"].as_array().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["contractId"], contract_id(0xdd));
+    assert_eq!(events[0]["txHash"], transaction_hash(7, 1));
+    assert_eq!(events[0]["topic"][0], symbol("transfer"));
+}
+
+#[tokio::test]
+async fn get_events_diagnostic() {
+    let server = TestServer::start().await;
+    let filters = json!([{ "type": "diagnostic" }]);
+    let result = server
+        .result("getEvents", json!({ "start_ledger": OLDEST_LEDGER, "filters": filters }))
+        .await;
+
+    // Only the diagnostic events themselves, numbered after the contract
+    // event they surround
+    let events = result["events"].as_array().unwrap();
+    let operation_id = (6i64 << 32) | (1 << 12) | 1;
+    let ids: Vec<_> = events.iter().map(|e| e["id"].clone()).collect();
+    assert_eq!(
+        ids,
+        vec![
+            json!(format!("{:019}-{:010}", operation_id, 1)),
+            json!(format!("{:019}-{:010}", operation_id, 2)),
+        ]
+    );
+    assert!(events.iter().all(|event| event["type"] == "diagnostic"));
+    assert_eq!(events[0]["topic"][0], symbol("fn_call"));
+}
+
+#[tokio::test]
+async fn get_events_from_v4_meta() {
+    // The fee bump in ledger 7 has the transaction meta of protocol 23 and
+    // later, where each operation's events are kept in its own meta
+    let transactions: Value = serde_json::from_str(include_str!("fixtures/horizon/transactions.json")).unwrap();
+    let transaction = transactions["_embedded"]["records"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|transaction| transaction["ledger"] == 7)
+        .unwrap();
+    let meta: TransactionMeta = xdr::from_base64(transaction["result_meta_xdr"].as_str().unwrap()).unwrap();
+    assert!(matches!(meta, TransactionMeta::V4(_)));
+
+    let server = TestServer::start().await;
+    let result = server
+        .result("getEvents", json!({ "start_ledger": 7, "pagination": { "limit": 2 } }))
+        .await;
+    let events = result["events"].as_array().unwrap();
+    let topics: Vec<_> = events.iter().map(|e| e["topic"][0].clone()).collect();
+    assert_eq!(topics, vec![json!(symbol("mint")), json!(symbol("transfer"))]);
+    assert!(events.iter().all(|event| event["contractId"] == contract_id(0xdd)));
+    assert_eq!(events[1]["id"], format!("{:019}-{:010}", (7i64 << 32) | (1 << 12) | 1, 1));
+}
+
+#[tokio::test]
+async fn get_events_as_json() {
+    let server = TestServer::start().await;
+    let result = server
+        .result("getEvents", json!({ "start_ledger": 7, "xdr_format": "json" }))
+        .await;
+
+    let event = &result["events"][0];
+    assert_eq!(event["topicJson"][0], json!({ "symbol": "mint" }));
+    assert!(event["valueJson"].is_object());
+    assert!(event.get("topic").is_none());
+}
+
+#[tokio::test]
+async fn send_transaction() {
+    let server = TestServer::start().await;
+    let result = server.result("sendTransaction", json!({ "transaction": PAYMENT_ENVELOPE })).await;
+    assert_eq!(result["status"], "PENDING");
+    assert_eq!(result["hash"], "cd".repeat(32));
+    assert_eq!(result["latestLedger"], LATEST_LEDGER);
+    assert_eq!(result["latestLedgerCloseTime"], "1714521650");
+
+    // The envelope is passed through to Horizon untouched
+    let submissions: Vec<_> = server
+        .horizon
+        .requests()
+        .into_iter()
+        .filter(|request| request.starts_with("POST /transactions_async"))
+        .collect();
+    assert_eq!(submissions.len(), 1);
+}
+
+#[tokio::test]
+async fn get_transaction() {
+    let server = TestServer::start().await;
+    let result = server.result("getTransaction", json!({ "hash": transaction_hash(5, 1) })).await;
+    assert_eq!(result["status"], "SUCCESS");
+    assert_eq!(result["ledger"], 5);
+    assert_eq!(result["applicationOrder"], 1);
+    assert_eq!(result["feeBump"], false);
+    assert_eq!(result["createdAt"], "1714521625");
+    assert_eq!(result["latestLedger"], LATEST_LEDGER);
+    assert_eq!(result["oldestLedger"], OLDEST_LEDGER);
+
+    let envelope = xdr::from_base64::<TransactionEnvelope>(result["envelopeXdr"].as_str().unwrap());
+    assert!(envelope.is_ok());
+    assert!(result["resultMetaXdr"].is_string());
+}
+
+#[tokio::test]
+async fn get_transaction_failed_and_fee_bump() {
+    let server = TestServer::start().await;
+    let failed = server.result("getTransaction", json!({ "hash": transaction_hash(6, 2) })).await;
+    assert_eq!(failed["status"], "FAILED");
+    assert_eq!(failed["applicationOrder"], 2);
+
+    let fee_bump = server.result("getTransaction", json!({ "hash": transaction_hash(7, 1) })).await;
+    assert_eq!(fee_bump["status"], "SUCCESS");
+    assert_eq!(fee_bump["feeBump"], true);
+}
+
+#[tokio::test]
+async fn get_transaction_not_found() {
+    let server = TestServer::start().await;
+    let result = server.result("getTransaction", json!({ "hash": "ff".repeat(32) })).await;
+    assert_eq!(result["status"], "NOT_FOUND");
+    assert_eq!(result["latestLedger"], LATEST_LEDGER);
+    assert!(result.get("ledger").is_none());
+    assert!(result.get("envelopeXdr").is_none());
+}
+
+#[tokio::test]
+async fn get_transactions() {
+    let server = TestServer::start().await;
+    let result = server.result("getTransactions", json!({ "start_ledger": 6 })).await;
+    assert_eq!(result["latestLedger"], LATEST_LEDGER);
+    assert_eq!(result["oldestLedger"], OLDEST_LEDGER);
+    assert_eq!(result["latestLedgerCloseTimestamp"], 1714521650);
+
+    let transactions = result["transactions"].as_array().unwrap();
+    let hashes: Vec<_> = transactions.iter().map(|t| t["txHash"].clone()).collect();
+    assert_eq!(
+        hashes,
+        vec![
+            json!(transaction_hash(6, 1)),
+            json!(transaction_hash(6, 2)),
+            json!(transaction_hash(7, 1)),
+            json!(transaction_hash(8, 1)),
+        ]
+    );
+    assert_eq!(transactions[1]["status"], "FAILED");
+    assert_eq!(transactions[2]["feeBump"], true);
+    assert_eq!(transactions[0]["createdAt"], 1714521630);
+    assert_eq!(result["cursor"], transactions_cursor(8, 1));
+}
+
+#[tokio::test]
+async fn get_fee_stats() {
+    let server = TestServer::start().await;
+    let result = server.result("getFeeStats", json!([])).await;
+    assert_eq!(result["latestLedger"], LATEST_LEDGER);
+    assert_eq!(result["inclusionFee"]["max"], "5000");
+    assert_eq!(result["inclusionFee"]["p50"], "100");
+    assert!(result.get("sorobanInclusionFee").is_none());
+
+    // Transactions are counted in the ledgers the stats were computed over
+    assert_eq!(result["inclusionFee"]["ledgerCount"], 5);
+    assert_eq!(result["inclusionFee"]["transactionCount"], "4");
+    let cursor = (i64::from(LATEST_LEDGER) + 1) << 32;
+    let requests = server.horizon.requests();
+    assert!(requests.contains(&format!("GET /ledgers?cursor={}&limit=5&order=desc", cursor)));
+}
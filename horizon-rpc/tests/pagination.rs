@@ -0,0 +1,249 @@
+// Paging through getLedgers, getTransactions and getEvents, and the limits
+// and ranges they enforce.
+
+mod common;
+
+use common::{server_config, TestServer, LATEST_LEDGER, OLDEST_LEDGER};
+use horizon_rpc::rpc::error::INVALID_PARAMS;
+use serde_json::{json, Value};
+
+// Calls a paginated method repeatedly, following the cursor of each page
+// until a page comes back empty, and returns the `field` of each record on
+// every page
+async fn pages(server: &TestServer, method: &str, params: Value, limit: u32, field: &str) -> Vec<Vec<Value>> {
+    let mut params = params;
+    params["pagination"] = json!({ "limit": limit });
+    let mut pages = Vec::new();
+    loop {
+        let result = server.result(method, params.clone()).await;
+        let records: Vec<Value> = result[records_field(method)]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|record| record[field].clone())
+            .collect();
+        if records.is_empty() {
+            return pages;
+        }
+        assert!(records.len() <= limit as usize);
+        pages.push(records);
+        params = json!({ "pagination": { "cursor": result["cursor"], "limit": limit } });
+    }
+}
+
+fn records_field(method: &str) -> &'static str {
+    match method {
+        "getLedgers" => "ledgers",
+        "getTransactions" => "transactions",
+        "getEvents" => "events",
+        _ => unreachable!("{} is not paginated", method),
+    }
+}
+
+fn transaction_hash(ledger: u8, index: u8) -> Value {
+    json!(format!("{:02x}{:02x}{}", ledger, index, "ab".repeat(30)))
+}
+
+#[tokio::test]
+async fn get_ledgers_pages() {
+    let server = TestServer::start().await;
+    let pages = pages(&server, "getLedgers", json!({ "start_ledger": OLDEST_LEDGER }), 4, "sequence").await;
+    assert_eq!(
+        pages,
+        vec![
+            vec![json!(2), json!(3), json!(4), json!(5)],
+            vec![json!(6), json!(7), json!(8), json!(9)],
+            vec![json!(10)],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn get_ledgers_cursor_at_end() {
+    let server = TestServer::start().await;
+    let cursor = (i64::from(LATEST_LEDGER) << 32).to_string();
+    let result = server
+        .result("getLedgers", json!({ "pagination": { "cursor": cursor } }))
+        .await;
+    assert_eq!(result["ledgers"], json!([]));
+    // The cursor stays put so the client can poll for new ledgers
+    assert_eq!(result["cursor"], cursor);
+}
+
+#[tokio::test]
+async fn get_ledgers_limits() {
+    let server = TestServer::start().await;
+    let default = server.result("getLedgers", json!({ "start_ledger": OLDEST_LEDGER })).await;
+    assert_eq!(default["ledgers"].as_array().unwrap().len(), 9);
+
+    for limit in [0, 201] {
+        let error = server
+            .call("getLedgers", json!({ "start_ledger": OLDEST_LEDGER, "pagination": { "limit": limit } }))
+            .await
+            .unwrap_err();
+        assert_eq!(error["code"], INVALID_PARAMS);
+        assert_eq!(error["message"], "Limit must be between 1 and 200");
+    }
+}
+
+#[tokio::test]
+async fn configured_page_limit() {
+    let server = TestServer::start_with(|rpc| rpc.with_max_page_limit(Some(2)), server_config()).await;
+
+    // The default shrinks to fit and larger limits are rejected
+    let result = server.result("getLedgers", json!({ "start_ledger": OLDEST_LEDGER })).await;
+    assert_eq!(result["ledgers"].as_array().unwrap().len(), 2);
+    let error = server
+        .call("getTransactions", json!({ "start_ledger": OLDEST_LEDGER, "pagination": { "limit": 3 } }))
+        .await
+        .unwrap_err();
+    assert_eq!(error["message"], "Limit must be between 1 and 2");
+}
+
+#[tokio::test]
+async fn get_ledgers_range() {
+    let server = TestServer::start().await;
+    for start_ledger in [OLDEST_LEDGER - 1, LATEST_LEDGER + 1] {
+        let error = server
+            .call("getLedgers", json!({ "start_ledger": start_ledger }))
+            .await
+            .unwrap_err();
+        assert_eq!(error["code"], INVALID_PARAMS);
+        assert_eq!(
+            error["message"],
+            "Start ledger must be between the oldest ledger 2 and the latest ledger 10"
+        );
+    }
+
+    let result = server.result("getLedgers", json!({ "start_ledger": LATEST_LEDGER })).await;
+    assert_eq!(result["ledgers"][0]["sequence"], LATEST_LEDGER);
+}
+
+#[tokio::test]
+async fn start_ledger_or_cursor() {
+    let server = TestServer::start().await;
+    for method in ["getLedgers", "getTransactions", "getEvents"] {
+        let both = json!({ "start_ledger": 5, "pagination": { "cursor": "21474836480" } });
+        assert_eq!(server.error_code(method, both).await, INVALID_PARAMS as i64, "{}", method);
+        assert_eq!(server.error_code(method, json!({})).await, INVALID_PARAMS as i64, "{}", method);
+        let invalid = json!({ "pagination": { "cursor": "not a cursor" } });
+        assert_eq!(server.error_code(method, invalid).await, INVALID_PARAMS as i64, "{}", method);
+    }
+}
+
+#[tokio::test]
+async fn get_transactions_pages() {
+    let server = TestServer::start().await;
+    let pages = pages(&server, "getTransactions", json!({ "start_ledger": OLDEST_LEDGER }), 2, "txHash").await;
+    assert_eq!(
+        pages,
+        vec![
+            vec![transaction_hash(5, 1), transaction_hash(6, 1)],
+            vec![transaction_hash(6, 2), transaction_hash(7, 1)],
+            vec![transaction_hash(8, 1)],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn get_transactions_from_start_ledger() {
+    let server = TestServer::start().await;
+    // Ledgers without transactions are skipped over
+    let result = server.result("getTransactions", json!({ "start_ledger": 8 })).await;
+    let hashes: Vec<_> = result["transactions"].as_array().unwrap().iter().map(|t| t["txHash"].clone()).collect();
+    assert_eq!(hashes, vec![transaction_hash(8, 1)]);
+
+    let result = server.result("getTransactions", json!({ "start_ledger": 9 })).await;
+    assert_eq!(result["transactions"], json!([]));
+    assert_eq!(result["cursor"], (9i64 << 32).to_string());
+}
+
+#[tokio::test]
+async fn get_transactions_limits() {
+    let server = TestServer::start().await;
+    for limit in [0, 201] {
+        let error = server
+            .call("getTransactions", json!({ "start_ledger": OLDEST_LEDGER, "pagination": { "limit": limit } }))
+            .await
+            .unwrap_err();
+        assert_eq!(error["code"], INVALID_PARAMS);
+    }
+    let error = server
+        .call("getTransactions", json!({ "start_ledger": LATEST_LEDGER + 1 }))
+        .await
+        .unwrap_err();
+    assert_eq!(error["code"], INVALID_PARAMS);
+}
+
+#[tokio::test]
+async fn get_events_pages() {
+    let server = TestServer::start().await;
+    let pages = pages(&server, "getEvents", json!({ "start_ledger": OLDEST_LEDGER }), 2, "ledger").await;
+    assert_eq!(pages, vec![vec![json!(6), json!(7)], vec![json!(7)]]);
+}
+
+#[tokio::test]
+async fn get_events_cursor_resumes_within_an_operation() {
+    let server = TestServer::start().await;
+    let first = server
+        .result("getEvents", json!({ "start_ledger": 7, "pagination": { "limit": 1 } }))
+        .await;
+    assert_eq!(first["events"][0]["id"], first["cursor"]);
+
+    let second = server
+        .result("getEvents", json!({ "pagination": { "cursor": first["cursor"], "limit": 1 } }))
+        .await;
+    let first_id = first["events"][0]["id"].as_str().unwrap();
+    let second_id = second["events"][0]["id"].as_str().unwrap();
+    assert_eq!(first_id.split('-').next(), second_id.split('-').next());
+    assert!(second_id > first_id);
+}
+
+#[tokio::test]
+async fn get_events_end_ledger() {
+    let server = TestServer::start().await;
+    // The end ledger is exclusive
+    let result = server
+        .result("getEvents", json!({ "start_ledger": OLDEST_LEDGER, "end_ledger": 7 }))
+        .await;
+    let ledgers: Vec<_> = result["events"].as_array().unwrap().iter().map(|e| e["ledger"].clone()).collect();
+    assert_eq!(ledgers, vec![json!(6)]);
+    assert_eq!(result["cursor"], format!("{:019}-{:010}", 7i64 << 32, 0));
+}
+
+#[tokio::test]
+async fn get_events_limits() {
+    let server = TestServer::start().await;
+    for limit in [0, 10_001] {
+        let error = server
+            .call("getEvents", json!({ "start_ledger": OLDEST_LEDGER, "pagination": { "limit": limit } }))
+            .await
+            .unwrap_err();
+        assert_eq!(error["code"], INVALID_PARAMS);
+        assert_eq!(error["message"], "Limit must be between 1 and 10000");
+    }
+
+    let error = server
+        .call("getEvents", json!({ "start_ledger": LATEST_LEDGER + 1 }))
+        .await
+        .unwrap_err();
+    assert_eq!(error["code"], INVALID_PARAMS);
+    assert_eq!(error["message"], "Start ledger 11 is after the latest ledger 10");
+}
+
+#[tokio::test]
+async fn get_events_scans_operation_pages() {
+    let server = TestServer::start().await;
+    server.result("getEvents", json!({ "start_ledger": OLDEST_LEDGER })).await;
+
+    // Every operation fits in one page from Horizon
+    let requests: Vec<_> = server
+        .horizon
+        .requests()
+        .into_iter()
+        .filter(|request| request.starts_with("GET /operations"))
+        .collect();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].contains("join=transactions"));
+    assert!(requests[0].contains(&format!("cursor={}", (i64::from(OLDEST_LEDGER) << 32) - 1)));
+}
@@ -0,0 +1,297 @@
+// The HTTP side of the server: health checks, metrics, tracing, CORS, and the
+// limits on clients and requests.
+
+mod common;
+
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use common::{server_config, TestServer, LATEST_LEDGER};
+use horizon_rpc::rate_limit::{RateLimiter, API_KEY_HEADER};
+use horizon_rpc::rpc::error::RATE_LIMITED;
+use horizon_rpc::server::ServerConfig;
+use jsonrpsee::server::BatchRequestConfig;
+use serde_json::{json, Value};
+
+fn request(id: u32, method: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": [] })
+}
+
+#[tokio::test]
+async fn health_check() {
+    let server = TestServer::start().await;
+    let response = server.client.get(format!("{}/health", server.url)).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "healthy");
+    assert_eq!(body["latestLedger"], LATEST_LEDGER);
+}
+
+#[tokio::test]
+async fn health_check_unhealthy() {
+    let server = TestServer::start_with(|rpc| rpc.with_max_ledger_age(Duration::from_secs(30)), server_config()).await;
+    let response = server.client.get(format!("{}/health", server.url)).send().await.unwrap();
+    assert_eq!(response.status(), 503);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "unhealthy");
+    assert_eq!(body["code"], -32001);
+}
+
+#[tokio::test]
+async fn metrics() {
+    let server = TestServer::start_with(|rpc| rpc, ServerConfig { serve_metrics: true, ..server_config() }).await;
+    server.result("getNetwork", json!([])).await;
+
+    let response = server.client.get(format!("{}/metrics", server.url)).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body = response.text().await.unwrap();
+    assert!(body.contains("method=\"getNetwork\""));
+}
+
+#[tokio::test]
+async fn metrics_not_served_by_default() {
+    let server = TestServer::start().await;
+    let response = server.client.get(format!("{}/metrics", server.url)).send().await.unwrap();
+    assert_ne!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn cors() {
+    let config = ServerConfig {
+        cors_origins: vec!["https://app.example".to_string()],
+        ..server_config()
+    };
+    let server = TestServer::start_with(|rpc| rpc, config).await;
+
+    let preflight = server
+        .client
+        .request(reqwest::Method::OPTIONS, &server.url)
+        .header("origin", "https://app.example")
+        .header("access-control-request-method", "POST")
+        .header("access-control-request-headers", "content-type")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(preflight.status(), 200);
+    assert_eq!(preflight.headers()["access-control-allow-origin"], "https://app.example");
+
+    let other = server
+        .client
+        .post(&server.url)
+        .header("origin", "https://other.example")
+        .json(&request(1, "getNetwork"))
+        .send()
+        .await
+        .unwrap();
+    assert!(other.headers().get("access-control-allow-origin").is_none());
+}
+
+#[tokio::test]
+async fn cors_off_by_default() {
+    let server = TestServer::start().await;
+    let response = server
+        .client
+        .post(&server.url)
+        .header("origin", "https://app.example")
+        .json(&request(1, "getNetwork"))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.headers().get("access-control-allow-origin").is_none());
+}
+
+#[tokio::test]
+async fn rate_limit_per_ip() {
+    let config = ServerConfig {
+        rate_limiter: RateLimiter::new(NonZeroU32::new(2), None, Vec::new()),
+        ..server_config()
+    };
+    let server = TestServer::start_with(|rpc| rpc, config).await;
+    server.result("getNetwork", json!([])).await;
+    server.result("getNetwork", json!([])).await;
+
+    let error = server.call("getNetwork", json!([])).await.unwrap_err();
+    assert_eq!(error["code"], RATE_LIMITED);
+    assert_eq!(error["data"]["retryAfter"], 1);
+}
+
+#[tokio::test]
+async fn rate_limit_per_api_key() {
+    let config = ServerConfig {
+        rate_limiter: RateLimiter::new(NonZeroU32::new(1), NonZeroU32::new(3), vec!["secret".to_string()]),
+        ..server_config()
+    };
+    let server = TestServer::start_with(|rpc| rpc, config).await;
+
+    let call = |key: &'static str| {
+        let request = server.client.post(&server.url).header(API_KEY_HEADER, key).json(&request(1, "getNetwork"));
+        async move { request.send().await.unwrap().json::<Value>().await.unwrap() }
+    };
+    for _ in 0..3 {
+        assert!(call("secret").await.get("result").is_some());
+    }
+    assert_eq!(call("secret").await["error"]["code"], RATE_LIMITED);
+
+    // Unknown keys count against the address
+    assert!(call("guess").await.get("result").is_some());
+    assert_eq!(call("guess").await["error"]["code"], RATE_LIMITED);
+}
+
+#[tokio::test]
+async fn rate_limit_forwarded_client() {
+    let config = ServerConfig {
+        rate_limiter: RateLimiter::new(NonZeroU32::new(1), None, Vec::new()),
+        trusted_proxies: vec!["127.0.0.1".parse().unwrap(), "10.0.0.1".parse().unwrap()],
+        ..server_config()
+    };
+    let server = TestServer::start_with(|rpc| rpc, config).await;
+
+    let call = |forwarded_for: &'static str| {
+        let request = server
+            .client
+            .post(&server.url)
+            .header("x-forwarded-for", forwarded_for)
+            .json(&request(1, "getNetwork"));
+        async move { request.send().await.unwrap().json::<Value>().await.unwrap() }
+    };
+    assert!(call("203.0.113.1").await.get("result").is_some());
+    assert_eq!(call("203.0.113.1").await["error"]["code"], RATE_LIMITED);
+    // Proxies are skipped, and addresses before the last untrusted one are
+    // ignored, since the client can set them
+    assert_eq!(call("203.0.113.1, 10.0.0.1").await["error"]["code"], RATE_LIMITED);
+    assert_eq!(call("198.51.100.1, 203.0.113.1").await["error"]["code"], RATE_LIMITED);
+    assert!(call("203.0.113.2").await.get("result").is_some());
+}
+
+#[tokio::test]
+async fn rate_limit_ignores_untrusted_forwarded_for() {
+    let config = ServerConfig {
+        rate_limiter: RateLimiter::new(NonZeroU32::new(1), None, Vec::new()),
+        ..server_config()
+    };
+    let server = TestServer::start_with(|rpc| rpc, config).await;
+
+    let call = |forwarded_for: &'static str| {
+        let request = server
+            .client
+            .post(&server.url)
+            .header("x-forwarded-for", forwarded_for)
+            .json(&request(1, "getNetwork"));
+        async move { request.send().await.unwrap().json::<Value>().await.unwrap() }
+    };
+    assert!(call("203.0.113.1").await.get("result").is_some());
+    assert_eq!(call("203.0.113.2").await["error"]["code"], RATE_LIMITED);
+}
+
+#[tokio::test]
+async fn batches() {
+    let config = ServerConfig {
+        batch_config: BatchRequestConfig::Limit(2),
+        ..server_config()
+    };
+    let server = TestServer::start_with(|rpc| rpc, config).await;
+
+    let responses = server.send(json!([request(1, "getNetwork"), request(2, "getLatestLedger")])).await;
+    let responses = responses.as_array().unwrap();
+    assert_eq!(responses.len(), 2);
+    assert!(responses.iter().all(|response| response.get("result").is_some()));
+
+    let response = server
+        .send(json!([request(1, "getNetwork"), request(2, "getNetwork"), request(3, "getNetwork")]))
+        .await;
+    assert_eq!(response["error"]["code"], -32010);
+}
+
+#[tokio::test]
+async fn request_body_limit() {
+    let config = ServerConfig {
+        max_request_body_size: 1024,
+        ..server_config()
+    };
+    let server = TestServer::start_with(|rpc| rpc, config).await;
+
+    let keys = vec!["A".repeat(100); 20];
+    let response = server
+        .client
+        .post(&server.url)
+        .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": "getLedgerEntries", "params": { "keys": keys } }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 413);
+}
+
+#[tokio::test]
+async fn calls_are_traced() {
+    // Tests run on a single thread, so the server's tasks log to this
+    // subscriber
+    let logs = Logs::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .json()
+        .with_current_span(true)
+        .with_span_list(true)
+        .with_writer(logs.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let server = TestServer::start().await;
+    server
+        .send(json!({ "jsonrpc": "2.0", "id": 7, "method": "getLedger", "params": { "sequence": 5 } }))
+        .await;
+
+    let lines = logs.lines();
+    let completed = lines
+        .iter()
+        .find(|line| line["fields"]["message"] == "RPC call succeeded")
+        .expect("the call is logged");
+    assert!(completed["fields"]["duration_ms"].is_u64());
+    assert_eq!(completed["span"]["name"], "rpc_call");
+    assert_eq!(completed["span"]["method"], "getLedger");
+    assert_eq!(completed["span"]["request_id"], "7");
+    assert_eq!(completed["span"]["params"], r#"{"sequence":5}"#);
+
+    // Requests to Horizon are logged within the call's span
+    let upstream = lines
+        .iter()
+        .filter_map(|line| line["spans"].as_array())
+        .flatten()
+        .find(|span| span["name"] == "horizon_request")
+        .expect("requests to Horizon have spans");
+    assert_eq!(upstream["method"], "GET");
+    assert_eq!(upstream["attempt"], 0);
+    assert!(upstream["url"].as_str().unwrap().ends_with("/ledgers/5"));
+}
+
+// Collects log lines written by a subscriber
+#[derive(Clone, Default)]
+struct Logs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl Logs {
+    fn lines(&self) -> Vec<Value> {
+        let logs = self.0.lock().unwrap();
+        String::from_utf8_lossy(&logs)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
+
+impl std::io::Write for Logs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for Logs {
+    type Writer = Logs;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
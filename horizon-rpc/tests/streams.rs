@@ -0,0 +1,110 @@
+// Horizon's server-sent event streams and the subscriptions built on them.
+
+mod common;
+
+use std::time::Duration;
+
+use common::{horizon_client, rpc_server, MockHorizon, LATEST_LEDGER};
+use futures::StreamExt;
+use horizon_rpc::rpc::StellarRpcApiServer;
+use jsonrpsee::core::EmptyServerParams;
+use serde_json::{json, Value};
+
+// Long enough for anything the mock sends, short enough that a test waiting
+// for a record that never comes fails instead of hanging
+const WAIT: Duration = Duration::from_secs(10);
+
+#[tokio::test]
+async fn stream_ledgers_from_now() {
+    let horizon = MockHorizon::start().await;
+    let stream = horizon_client(&horizon).stream_ledgers("now").await.unwrap();
+    let ledgers: Vec<_> = tokio::time::timeout(WAIT, stream.take(2).collect::<Vec<_>>()).await.unwrap();
+    let sequences: Vec<_> = ledgers.into_iter().map(|ledger| ledger.unwrap().sequence).collect();
+    assert_eq!(sequences, vec![LATEST_LEDGER - 1, LATEST_LEDGER]);
+}
+
+#[tokio::test]
+async fn stream_effects_from_cursor() {
+    let horizon = MockHorizon::start().await;
+    let stream = horizon_client(&horizon).stream_effects("21474840577-1").await.unwrap();
+    let effects: Vec<_> = tokio::time::timeout(WAIT, stream.take(3).collect::<Vec<_>>()).await.unwrap();
+    let tokens: Vec<_> = effects.into_iter().map(|effect| effect.unwrap().paging_token).collect();
+    assert_eq!(tokens, vec!["21474840577-2", "34359742465-1", "34359742465-2"]);
+}
+
+#[tokio::test]
+async fn stream_reconnects_after_the_last_record() {
+    let horizon = MockHorizon::start().await;
+    let stream = horizon_client(&horizon).stream_ledgers("now").await.unwrap();
+    futures::pin_mut!(stream);
+    for _ in 0..2 {
+        tokio::time::timeout(WAIT, stream.next()).await.unwrap().unwrap().unwrap();
+    }
+
+    // Horizon closes the connection after the fixtures, and rejecting the
+    // reconnection ends the stream
+    horizon.fail("/ledgers", 404, 1);
+    let next = tokio::time::timeout(WAIT, stream.next()).await.unwrap();
+    assert!(next.unwrap().is_err());
+    assert!(tokio::time::timeout(WAIT, stream.next()).await.unwrap().is_none());
+
+    let last_cursor = format!("cursor={}", i64::from(LATEST_LEDGER) << 32);
+    let requests = horizon.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0].contains("cursor=now"));
+    assert!(requests[1].contains(&last_cursor));
+}
+
+#[tokio::test]
+async fn stream_fails_if_horizon_rejects_it() {
+    let horizon = MockHorizon::start().await;
+    horizon.fail("/ledgers", 404, 1);
+    assert!(horizon_client(&horizon).stream_ledgers("now").await.is_err());
+}
+
+#[tokio::test]
+async fn subscribe_ledgers() {
+    let horizon = MockHorizon::start().await;
+    let rpc = rpc_server(&horizon).into_rpc();
+    let mut subscription = rpc
+        .subscribe_unbounded("subscribeLedgers", EmptyServerParams::new())
+        .await
+        .unwrap();
+
+    let mut sequences = Vec::new();
+    for _ in 0..2 {
+        let (ledger, _) = tokio::time::timeout(WAIT, subscription.next::<Value>()).await.unwrap().unwrap().unwrap();
+        sequences.push(ledger["sequence"].clone());
+    }
+    assert_eq!(sequences, vec![json!(LATEST_LEDGER - 1), json!(LATEST_LEDGER)]);
+}
+
+#[tokio::test]
+async fn subscribe_events() {
+    let horizon = MockHorizon::start().await;
+    let rpc = rpc_server(&horizon).into_rpc();
+    // The latest operations are the fee bumped contract call and a payment
+    let mut subscription = rpc
+        .subscribe_unbounded("subscribeEvents", [json!([{ "type": "contract" }])])
+        .await
+        .unwrap();
+
+    let mut events = Vec::new();
+    for _ in 0..2 {
+        let (event, _) = tokio::time::timeout(WAIT, subscription.next::<Value>()).await.unwrap().unwrap().unwrap();
+        events.push(event);
+    }
+    assert_eq!(events[0]["ledger"], 7);
+    assert_eq!(events[0]["id"], format!("{:019}-{:010}", (7i64 << 32) | (1 << 12) | 1, 0));
+    assert_eq!(events[1]["id"], format!("{:019}-{:010}", (7i64 << 32) | (1 << 12) | 1, 1));
+}
+
+#[tokio::test]
+async fn subscribe_events_rejects_invalid_filters() {
+    let horizon = MockHorizon::start().await;
+    let rpc = rpc_server(&horizon).into_rpc();
+    let result = rpc
+        .subscribe_unbounded("subscribeEvents", [json!([{ "type": "unknown" }])])
+        .await;
+    assert!(result.is_err());
+}